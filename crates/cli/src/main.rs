@@ -21,14 +21,12 @@
 // SOFTWARE.
 
 use std::{
-    env, path::Path, fs::File, io::Read,
+    env, fs, path::{Path, PathBuf},
     collections::HashMap, collections::BTreeSet,
 };
 
-use farvm_utils::{diag,pool, Emit};
-use farvm_tree;
-use farvm_compiler;
-use farvm_vm;
+use farvm_utils::{diag,pool,source, Emit};
+use farvm_vm::Program;
 
 const VERSION:&str = "1.0.0-re2021";
 const USAGE:&str =
@@ -52,41 +50,50 @@ Examples use:
     run an file:
         farvm main.fa
     build these sources:
-        farvm build -o a.fao a.fa b.fa c.fa
+        farvm build a.fa b.fa c.fa
 ";
 const USAGE_BUILD:&str =
 "build [Options] <Source-Path>...
+    the first source is the entry, the others and the imported are modules
+
+Options:
+    -I <Dir>    a dir to find the imported modules, it can be given many times
+    -I=<Dirs>   the dirs split like PATH, like '-I=lib:vendor' (';' on Windows)
+    -o <Path>   the compiled-object file, it's rejected until the object format is done
+
+Module search:
+    the module 'geo.shapes' is the file 'geo/shapes.fa', it's found in order:
+    the dir of the importer, the -I dirs, the dirs in env FARVM_PATH (split like PATH),
+    then the 'lib' dir beside the entry source
 ";
+const USAGE_RUN:&str =
+"run [Options] <Source-Path>...
+    the sources are built like 'build', then the 'main' is run
 
-fn read_to_string(filepath:&str) -> Result<String, String> {
-    let target_path = Path::new(filepath);
-    if target_path.is_file() {
-        match File::open(&target_path) {
-            Err(a) => {
-                Err(format!("{:?} for open '{}'", a, target_path.display()))
-            }
-            Ok(mut file) => {
-                let mut buf = String::new();
-                match file.read_to_string(&mut buf) {
-                    Err(a) => {
-                        Err(format!("{:?} for read '{}'", a, target_path.display()))
-                    }
-                    Ok(_) => {
-                        Ok(buf)
-                    }
-                }
-            }
-        }
-    } else {
-        Err(format!("it's not a file-path for '{}'", target_path.display()))
-    }
-}
+Options:
+    -I <Dir>    a dir to find the imported modules, it can be given many times
+    -I=<Dirs>   the dirs split like PATH, see 'farvm help build' for the order of search
+";
+const USAGE_EMIT:&str =
+"emit <Phase> [Options] <Source-Path>
+    the output is printed, and the diagnostics are printed to stderr
+
+Phases:
+    -tree           the syntax tree of source
+
+Options:
+    -format=<Name>  sexp (default) the canonical S-expression form
+                    json the stable JSON with spans, the schema is in farvm_tree/src/json_v1.rs
+    -expand         the tree after macros expanded
+";
 
 #[derive(Debug)]
 #[allow(dead_code)]
 struct Params {
     confs: HashMap<String, String>,
     switchs: BTreeSet<String>,
+    includes: Vec<String>,          // the "-I dir" and "-I=dirs" in order
+    output: Option<String>,         // the "-o path"
     input_paths: Vec<String>,
 }
 impl Params {
@@ -94,55 +101,139 @@ impl Params {
         Params {
             confs: HashMap::new(),
             switchs: BTreeSet::new(),
+            includes: Vec::new(),
+            output: None,
             input_paths: Vec::new(),
         }
     }
 }
 
+impl Params {
+    // the dirs to find modules: the -I dirs, the FARVM_PATH, then the "lib" beside the entry if it's exists
+    fn search_paths(&self) -> Vec<PathBuf> {
+        let mut paths:Vec<PathBuf> = self.includes.iter().flat_map(env::split_paths).collect();
+        if let Some(a) = env::var_os("FARVM_PATH") {
+            paths.extend(env::split_paths(&a));
+        }
+        if let Some(entry) = self.input_paths.first() {
+            let lib = Path::new(entry).parent().unwrap_or(Path::new("")).join("lib");
+            if lib.is_dir() {
+                paths.push(lib);
+            }
+        }
+        paths
+    }
+}
+
+// load the sources and their modules, then compile to a program, the diag is printed if it has any
+fn compile(params:&Params) -> Option<Program> {
+    let mut the_source = source::make();
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let search = params.search_paths();
+    let the_tree = match farvm_compiler::load(&params.input_paths, &search, &mut the_source, &mut unique_pool, &mut the_diag) {
+        Ok(a) => {a}
+        Err(e) => {println!("Error: '{}'", e); return None}
+    };
+    if the_diag.has_error() {
+        println!("{}", the_diag.render(&the_source));
+        return None
+    }
+    let program = farvm_compiler::build(&the_tree, &unique_pool, &mut the_diag);
+    if the_diag.has_error() || !the_diag.warnings().is_empty() {
+        println!("{}", the_diag.render(&the_source));
+    }
+    if the_diag.has_error() {
+        return None
+    }
+    Some(program)
+}
+
+// the compiled-object file isn't written now, so it's only check the sources
 fn subcommand_build(params:Params) {
-    println!("build {:?}", params)
+    // there is no format of compiled-object yet, so it isn't written as if it's done
+    if let Some(path) = &params.output {
+        println!("Error: can't write '{}', the compiled-object file isn't supported yet", path); return
+    }
+    if let Some(program) = compile(&params) {
+        println!("ok, {} function(s) and {} global(s)", program.functions.len(), program.globals.len())
+    }
 }
 
 fn subcommand_run(params:Params) {
-    match read_to_string(params.input_paths[0].as_str()) {
-        Err(e) => {println!("Error: '{}'", e)}
-        Ok(code) => {
-            let mut unique_pool = pool::make();
-            let mut the_diag = diag::make();
-            let the_tree = farvm_tree::build_v1(&code, &mut unique_pool, &mut the_diag);
-            if the_diag.has_error() {
-                println!("{}", the_diag)
-            } else {
-                println!("{}", the_tree.emit(&unique_pool));
-                let program = farvm_compiler::build(&the_tree);
-                let mut cpu = farvm_vm::make();
-                farvm_vm::run(&mut cpu, &program);
-            }
+    if let Some(program) = compile(&params) {
+        let mut cpu = farvm_vm::make();
+        farvm_std::install(&mut cpu);
+        if let Err(e) = farvm_vm::run(&mut cpu, &program) {
+            println!("Error: {}", e)
         }
     }
 }
 
 fn subcommand_emit(params:Params) {
-    println!("emit {:?}", params)
+    if !params.switchs.contains("TREE") {
+        println!("Error: lack a phase, like '-tree'"); return
+    }
+    let format = params.confs.get("format").map_or("sexp", |a| a.as_str());
+    if format != "sexp" && format != "json" {
+        println!("Error: unknown format '{}'", format); return
+    }
+    let path = &params.input_paths[0];
+    let code = match fs::read(path) {
+        Ok(a) => {a}
+        Err(e) => {println!("Error: '{}' {}", path, e); return}
+    };
+    let mut the_source = source::make();
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let file = the_source.add_file(path, code);
+    let mut the_tree = farvm_tree::build_v1(the_source.code(file), file, &mut unique_pool, &mut the_diag);
+    if params.switchs.contains("EXPAND") {
+        the_tree = farvm_tree::expand_v1(the_tree, &mut unique_pool, &mut the_diag);
+    }
+    // the tree is printed even it has errors, the bad parts are (error)
+    if the_diag.has_error() || !the_diag.warnings().is_empty() {
+        eprintln!("{}", the_diag.render(&the_source));
+    }
+    match format {
+        "json" => {println!("{}", farvm_tree::json_v1(&the_tree, &unique_pool, &the_source))}
+        _ => {println!("{}", the_tree.emit(&unique_pool))}
+    }
 }
 
 fn make_params(args:&[String]) -> Params {
     let mut params = Params::new();
     let mut it = args.iter();
     while let Some(a) = it.next() {
-        if a.as_bytes()[0] == ('-' as u8) {
+        if a == "-I" {
+            match it.next() {
+                Some(dir) => {params.includes.push(dir.clone())}
+                None => {panic!("Error: option '-I' lacks a dir")}
+            }
+            continue
+        }
+        if a == "-o" {
+            match it.next() {
+                Some(path) => {params.output = Some(path.clone())}
+                None => {panic!("Error: option '-o' lacks a path")}
+            }
+            continue
+        }
+        if a.as_bytes()[0] == b'-' {
             match a.split_once('=') {
+                Some(("-I", v)) if !v.is_empty() => {params.includes.push(String::from(v))}
+                Some(("-o", v)) if !v.is_empty() => {params.output = Some(String::from(v))}
                 Some((k,v)) => {
-                    if v.len() > 0 {
+                    if !v.is_empty() {
                         let key = String::from(&k[1..]);
                         params.confs.insert(key, String::from(v));
                     } else {
-                        panic!("Error: option '{}' it lask value", a)
+                        panic!("Error: option '{}' lacks a value", a)
                     }
                 }
                 None => {
                     let switch_name = &a[1..].to_uppercase();
-                    if switch_name.len() > 0 {
+                    if !switch_name.is_empty() {
                         params.switchs.insert(String::from(switch_name));
                     }
                 }
@@ -151,10 +242,10 @@ fn make_params(args:&[String]) -> Params {
             params.input_paths.push(a.clone()); break
         }
     }
-    while let Some(a) = it.next() {
+    for a in it {
         params.input_paths.push(a.clone())
     }
-    if params.input_paths.len() > 0 {
+    if !params.input_paths.is_empty() {
         params
     } else {
         panic!("Error: lack an input path")
@@ -164,7 +255,7 @@ fn make_params(args:&[String]) -> Params {
 fn main() {
     let args:Vec<String> = env::args().collect();
     let args = &args[1..];
-    if args.len() > 0 {
+    if !args.is_empty() {
         let subcommand = args[0].to_uppercase();
         match subcommand.as_str() {
             "BUILD" => {subcommand_build(make_params(&args[1..]))}
@@ -180,6 +271,8 @@ fn main() {
                 };
                 let usage = match subcommand_name.as_str() {
                     "BUILD" => {USAGE_BUILD}
+                    "RUN" => {USAGE_RUN}
+                    "EMIT" => {USAGE_EMIT}
                     _ => {USAGE}
                 };
                 println!("{}", usage)
@@ -0,0 +1,79 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{fs, path::PathBuf, process::Command};
+
+const HELLO:&str = "import md\n(main) = (println! (md.twice 21))\n";
+const MD:&str = "module md\npub (twice a) = (* a 2)\n";
+
+// the files are written to a new dir in temp, it's removed by the caller
+fn write(dir:&str, files:&[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("farvm-cli-{}-{}", dir, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    for (path, code) in files {
+        fs::write(root.join(path), code).unwrap();
+    }
+    root
+}
+
+// the stdout and stderr of farvm run in dir
+fn farvm(dir:&PathBuf, args:&[&str]) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_farvm")).current_dir(dir).args(args).output().unwrap();
+    (String::from_utf8_lossy(&output.stdout).into_owned(), String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[test]
+fn build_and_run() {
+    let dir = write("build", &[("hello.fa", HELLO), ("md.fa", MD)]);
+    let (out, _) = farvm(&dir, &["build", "hello.fa", "md.fa"]);
+    assert!(out.starts_with("ok, "), "{}", out);
+    let (out, _) = farvm(&dir, &["run", "hello.fa", "md.fa"]);
+    assert_eq!(out, "42\n");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+// the path after "-o" isn't an input, and the object isn't pretended to be written
+#[test]
+fn output_option() {
+    let dir = write("output", &[("hello.fa", HELLO), ("md.fa", MD)]);
+    let (out, _) = farvm(&dir, &["build", "-o", "a.fao", "hello.fa", "md.fa"]);
+    assert_eq!(out, "Error: can't write 'a.fao', the compiled-object file isn't supported yet\n");
+    let (out, _) = farvm(&dir, &["build", "-o=a.fao", "hello.fa", "md.fa"]);
+    assert_eq!(out, "Error: can't write 'a.fao', the compiled-object file isn't supported yet\n");
+    assert!(!dir.join("a.fao").exists());
+    let (_, err) = farvm(&dir, &["build", "-o"]);
+    assert!(err.contains("Error: option '-o' lacks a path"), "{}", err);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn include_option() {
+    let dir = write("include", &[("hello.fa", HELLO)]);
+    fs::create_dir_all(dir.join("vendor")).unwrap();
+    fs::write(dir.join("vendor").join("md.fa"), MD).unwrap();
+    let (out, _) = farvm(&dir, &["run", "-I", "vendor", "hello.fa"]);
+    assert_eq!(out, "42\n");
+    let (_, err) = farvm(&dir, &["run", "-I"]);
+    assert!(err.contains("Error: option '-I' lacks a dir"), "{}", err);
+    let _ = fs::remove_dir_all(&dir);
+}
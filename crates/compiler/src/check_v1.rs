@@ -0,0 +1,697 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+use farvm_tree::{Literal, Node, NodeId, NumberSuffix};
+use farvm_utils::{Diag, Emit, Pool, Span};
+use farvm_utils::pool::Id;
+use super::resolve_v1::{self, Binding, Resolution};
+use super::types_v1::{self, Type};
+use super::dispatch_v1::{self, Method, Selection};
+
+// The types of the expressions and globals, the expression is by the id of node
+//   the methods of dispatch group are by the id of group, and the calls bound at compile time by the id of call
+pub struct Typing<'t> {
+    types: HashMap<NodeId<'t>, Type>,
+    globals: HashMap<Id, Type>,
+    groups: HashMap<NodeId<'t>, Vec<Method<'t>>>,
+    calls: HashMap<NodeId<'t>, NodeId<'t>>,
+}
+
+impl<'t> Typing<'t> {
+    pub fn type_of(&self, node:&'t Node) -> Option<&Type> {
+        self.types.get(&node.id())
+    }
+    pub fn global_of(&self, name:Id) -> Option<&Type> {
+        self.globals.get(&name)
+    }
+    // the methods are sorted by specificity, the more specific is first
+    pub fn methods_of(&self, group:&'t Node) -> &[Method<'t>] {
+        self.groups.get(&group.id()).map_or(&[], |a| a.as_slice())
+    }
+    // the id of method, if the call is bound to it at compile time
+    pub fn method_of(&self, call:&'t Node) -> Option<NodeId<'t>> {
+        self.calls.get(&call.id()).copied()
+    }
+}
+
+struct Object<'a, 't> {
+    pool: &'a Pool,
+    diag: &'a mut Diag,
+    resolution: &'a Resolution<'t>,
+    items: HashMap<NodeId<'t>, &'t Node>,       // the top level by id, the globals are checked when it's used
+    names: HashMap<Id, NodeId<'t>>,
+    globals: HashMap<NodeId<'t>, Type>,
+    checking: Vec<NodeId<'t>>,
+    locals: HashMap<NodeId<'t>, Type>,          // the parameters and locals by the id of Param or Assign
+    generics: Vec<Vec<Id>>,
+    types: HashMap<NodeId<'t>, Type>,
+    vars: Vec<Var>,
+    groups: HashMap<NodeId<'t>, Vec<Method<'t>>>,
+    calls: HashMap<NodeId<'t>, NodeId<'t>>,
+}
+
+// The unknown type of a binding, it's solved by the uses in the item it's created
+//   the required is the 'auto', it's ambiguous if not solved, the others are Any
+struct Var {
+    solved: Option<Type>,
+    span: Span,
+    what: String,
+    required: bool,
+}
+
+// check the resolved tree, the mismatched are errors with the expected and found types
+//   the global is checked at first used, so it's type is known before the users of it
+//   the recursive use of a global is Any, but the parameters of function are known
+//   the unannotated parameters, 'auto' and result of function are inferred in the top level item,
+//   the unsolved are Any after the item, but the 'auto' are errors, so the [] is a List of Any if it isn't used
+pub fn pass<'t>(tree:&'t Node, resolution:&Resolution<'t>, pool:&Pool, diag:&mut Diag) -> Typing<'t> {
+    let items = match tree {
+        Node::Module{items, ..} => {items.as_slice()}
+        _ => {std::slice::from_ref(tree)}
+    };
+    let mut me = Object {
+        pool, diag, resolution,
+        items: items.iter().map(|a| (a.id(), a)).collect(),
+        names: HashMap::new(), globals: HashMap::new(), checking: Vec::new(),
+        locals: HashMap::new(), generics: Vec::new(), types: HashMap::new(), vars: Vec::new(),
+        groups: HashMap::new(), calls: HashMap::new(),
+    };
+    for item in items {
+        if let Node::Define{name, ..} | Node::DispatchGroup{name, ..} | Node::Assign{name, ..} = item {
+            me.names.entry(*name).or_insert(item.id());
+        }
+    }
+    for item in items {
+        match item {
+            Node::Define{..} | Node::Assign{..} | Node::DispatchGroup{..} => {me.global(item.id());}
+            _ => {
+                let from = me.vars.len();
+                me.expr(item);
+                me.settle(from);
+            }
+        }
+    }
+    let types = me.types.iter().map(|(id, a)| (*id, me.resolve(a))).collect();
+    let mut globals = HashMap::new();
+    for (name, id) in &me.names {
+        if let Some(a) = me.globals.get(id) {
+            globals.insert(*name, a.clone());
+        }
+    }
+    let groups = me.groups.iter().map(|(id, methods)| {
+        let methods = methods.iter().map(|a| Method { ret: me.resolve(&a.ret), ..a.clone() }).collect();
+        (*id, methods)
+    }).collect();
+    Typing { types, globals, groups, calls: me.calls }
+}
+
+impl<'a, 't> Object<'a, 't> {
+    fn name(&self, name:Id) -> String {self.pool.str_at(name)}
+    fn show(&self, a:&Type) -> String {self.resolve(a).emit(self.pool)}
+
+    fn fresh(&mut self, span:Span, what:String, required:bool) -> Type {
+        self.vars.push(Var { solved: None, span, what, required });
+        Type::Var(self.vars.len() - 1)
+    }
+
+    // the type with the solved vars replaced
+    fn resolve(&self, a:&Type) -> Type {
+        match a {
+            Type::Var(var) => {
+                match &self.vars[*var].solved {
+                    Some(a) => {self.resolve(a)}
+                    None => {a.clone()}
+                }
+            }
+            Type::List(a) => {Type::List(Box::new(self.resolve(a)))}
+            Type::Function(params, ret) => {
+                Type::Function(params.iter().map(|a| self.resolve(a)).collect(), Box::new(self.resolve(ret)))
+            }
+            Type::Object{protos, slots} => {
+                Type::Object{protos: protos.clone(), slots: slots.iter().map(|(name, a)| (*name, self.resolve(a))).collect()}
+            }
+            _ => {a.clone()}
+        }
+    }
+
+    // the var in itself is never solved, like 'a' is the List of 'a'
+    fn solve(&mut self, var:usize, a:Type) {
+        self.vars[var].solved = Some(if a.contains_var(var) {Type::Any} else {a});
+    }
+
+    // found can be used as expected, the vars in them are solved to the other
+    //   the Any solve nothing, so the 'auto' used as Any is still ambiguous
+    fn constrain(&mut self, found:&Type, expected:&Type) -> bool {
+        let (found, expected) = (self.resolve(found), self.resolve(expected));
+        match (&found, &expected) {
+            (Type::Var(a), Type::Var(b)) if a == b => {true}
+            (Type::Any, _) | (_, Type::Any) => {true}
+            (_, Type::Var(var)) => {self.solve(*var, found); true}
+            (Type::Var(var), _) => {self.solve(*var, expected); true}
+            (Type::List(a), Type::List(b)) => {self.constrain(a, b)}
+            (Type::Function(ps, r), Type::Function(qs, s)) if ps.len() == qs.len() => {
+                let params = qs.iter().zip(ps).fold(true, |ok, (q, p)| self.constrain(q, p) && ok);
+                self.constrain(r, s) && params
+            }
+            _ => {found.is_subtype(&expected)}
+        }
+    }
+
+    // the vars from the index are unsolved after the item, the required are ambiguous
+    fn settle(&mut self, from:usize) {
+        for var in from..self.vars.len() {
+            if self.vars[var].solved.is_some() {
+                continue
+            }
+            if self.vars[var].required {
+                let describe = format!("can't infer the type of {}, it needs a type annotation", self.vars[var].what);
+                self.diag.push_error(self.vars[var].span, &describe);
+            }
+            self.vars[var].solved = Some(Type::Any);
+        }
+    }
+
+    // the type of global by the id of it's definition
+    fn global(&mut self, id:NodeId<'t>) -> Type {
+        if let Some(a) = self.globals.get(&id) {
+            return a.clone()
+        }
+        if self.checking.contains(&id) {
+            return Type::Any
+        }
+        let Some(item) = self.items.get(&id).copied() else {
+            return Type::Any
+        };
+        self.checking.push(id);
+        let from = self.vars.len();
+        let ty = match item {
+            Node::Define{..} => {self.function(item)}
+            Node::Assign{name, ty, value, ..} => {
+                let ty = self.binding(*name, ty, value);
+                // the global of object literal is a prototype, it's name is the type of derived objects
+                match (value.as_ref(), ty) {
+                    (Node::ObjectLiteral{..}, Type::Object{mut protos, slots}) => {
+                        protos.insert(0, *name);
+                        Type::Object{protos, slots}
+                    }
+                    (_, ty) => {ty}
+                }
+            }
+            Node::DispatchGroup{..} => {self.group(item)}
+            _ => {Type::Any}
+        };
+        self.settle(from);
+        let ty = self.resolve(&ty);
+        self.checking.retain(|a| *a != id);
+        self.globals.insert(id, ty.clone());
+        ty
+    }
+
+    // the type of "name:Type = value", it's the type of value if unannotated
+    fn binding(&mut self, name:Id, ty:&'t Option<Box<Node>>, value:&'t Node) -> Type {
+        let expected = match ty.as_deref() {
+            Some(Node::AutoType{span}) => {
+                let what = format!("'{}'", self.name(name));
+                self.fresh(*span, what, true)
+            }
+            Some(a) => {self.annotation(a)}
+            None => {return self.expr(value)}
+        };
+        let note = ty.as_ref().map(|a| a.span());
+        self.check(value, &expected, note.map(|a| (a, "the type is annotated here")));
+        expected
+    }
+
+    // the params are known before the body, so a recursive call is checked with them
+    fn function(&mut self, node:&'t Node) -> Type {
+        let Node::Define{span, name, generics, params, body} = node else {
+            return Type::Any
+        };
+        self.generics.push(generics.iter().filter_map(|a| match a {
+            Node::TypeParam{name, ..} => {Some(*name)}
+            _ => {None}
+        }).collect());
+        let types = self.params(params);
+        let ret = self.result(node.id(), *span, *name, &types, body);
+        self.generics.pop();
+        Type::Function(types, Box::new(ret))
+    }
+
+    // the types of parameters, they're bound as locals
+    fn params(&mut self, params:&'t [Node]) -> Vec<Type> {
+        let mut types = Vec::new();
+        for param in params {
+            let Node::Param{name, ty, ..} = param else {continue};
+            let what = format!("parameter '{}'", self.name(*name));
+            let ty = match ty.as_deref() {
+                Some(Node::AutoType{..}) => {self.fresh(param.span(), what, true)}
+                Some(a) => {self.annotation(a)}
+                None => {self.fresh(param.span(), what, false)}
+            };
+            self.locals.insert(param.id(), ty.clone());
+            types.push(ty);
+        }
+        types
+    }
+
+    // the function is it's own type in the body, so the recursive call is checked
+    fn result(&mut self, id:NodeId<'t>, span:Span, name:Id, types:&[Type], body:&'t Node) -> Type {
+        let what = format!("the result of '{}'", self.name(name));
+        let ret = self.fresh(span, what, false);
+        self.globals.entry(id).or_insert(Type::Function(types.to_vec(), Box::new(ret.clone())));
+        let found = self.expr(body);
+        self.constrain(&found, &ret);
+        self.globals.remove(&id);
+        ret
+    }
+
+    // the signatures are known before the bodies, so the methods can call the group
+    fn group(&mut self, node:&'t Node) -> Type {
+        let Node::DispatchGroup{span, strategy, name, variadic, methods} = node else {
+            return Type::Any
+        };
+        if self.name(*strategy) != "apply" {
+            let describe = format!("unknown strategy '{}' of dispatch, it's only 'apply' now", self.name(*strategy));
+            self.diag.push_error(*span, &describe);
+        }
+        let mut checked = Vec::new();
+        for method in methods {
+            if let Node::Define{span, params, ..} = method {
+                let params = self.params(params);
+                checked.push(Method { id: method.id(), span: *span, params, ret: Type::Any });
+            }
+        }
+        self.globals.insert(node.id(), Type::Dispatch(*name));
+        self.groups.insert(node.id(), checked.clone());
+        for (method, checked) in methods.iter().filter(|a| matches!(a, Node::Define{..})).zip(checked.iter_mut()) {
+            if let Node::Define{span, name, body, ..} = method {
+                let types = checked.params.clone();
+                checked.ret = self.result(method.id(), *span, *name, &types, body);
+            }
+        }
+        for a in checked.iter_mut() {
+            a.params = a.params.iter().map(|b| self.resolve(b)).collect();
+            a.ret = self.resolve(&a.ret);
+        }
+        let sorted = dispatch_v1::check_group(&self.name(*name), *variadic, checked, self.pool, self.diag);
+        self.groups.insert(node.id(), sorted);
+        Type::Dispatch(*name)
+    }
+
+    // the type written in source, the type parameters of generic are Any now
+    fn annotation(&mut self, node:&Node) -> Type {
+        match node {
+            Node::TypeRef{span, name} => {
+                if self.generics.iter().any(|a| a.contains(name)) {
+                    return Type::Any
+                }
+                let text = self.name(*name);
+                if let Some(a) = Type::from_name(&text) {
+                    return a
+                }
+                if let Some(id) = self.names.get(name).copied() {
+                    if let ty @ Type::Object{..} = self.global(id) {
+                        return ty
+                    }
+                }
+                self.unknown_type(*span, &text);
+                Type::Any
+            }
+            Node::Generic{span, base, args} => {
+                let args:Vec<Type> = args.iter().map(|a| self.annotation(a)).collect();
+                match base.as_ref() {
+                    Node::TypeRef{name, ..} if self.name(*name) == "List" => {
+                        if args.len() == 1 {
+                            Type::List(Box::new(args[0].clone()))
+                        } else {
+                            let describe = format!("type 'List' expects 1 parameter but found {}", args.len());
+                            self.diag.push_error(*span, &describe);
+                            Type::Any
+                        }
+                    }
+                    Node::TypeRef{name, ..} => {
+                        let describe = format!("type '{}' has no parameter", self.name(*name));
+                        self.diag.push_error(*span, &describe);
+                        Type::Any
+                    }
+                    _ => {Type::Any}
+                }
+            }
+            Node::FunctionType{params, ret, ..} => {
+                let params = params.iter().map(|a| self.annotation(a)).collect();
+                Type::Function(params, Box::new(self.annotation(ret)))
+            }
+            Node::AutoType{span} => {self.fresh(*span, String::from("'auto'"), true)}
+            _ => {Type::Any}
+        }
+    }
+
+    fn unknown_type(&mut self, span:Span, name:&str) {
+        let mut candidates:Vec<String> = types_v1::NAMES.iter().map(|a| String::from(*a)).collect();
+        candidates.extend(self.names.keys().map(|a| self.name(*a)));
+        let mut describe = format!("unknown type '{}'", name);
+        if let Some(similar) = resolve_v1::similar_of(name, &candidates) {
+            describe += &format!(", did you mean '{}'?", similar);
+        }
+        self.diag.push_error(span, &describe);
+    }
+
+    fn mismatch(&mut self, span:Span, expected:&Type, found:&Type) -> &mut farvm_utils::diag::DiagUnit {
+        let describe = format!("mismatched types: expected {} but found {}", self.show(expected), self.show(found));
+        self.diag.push_error(span, &describe)
+    }
+
+    // the node should be used as expected, the note is where the expected is from
+    fn check(&mut self, node:&'t Node, expected:&Type, note:Option<(Span, &str)>) -> Type {
+        if let Some(found) = self.fixed_literal(node, expected) {
+            return found
+        }
+        let found = self.expr(node);
+        if !self.constrain(&found, expected) {
+            let error = self.mismatch(node.span(), expected, &found);
+            if let Some((span, describe)) = note {
+                error.note(span, describe);
+            }
+        }
+        found
+    }
+
+    // the number literal without suffix takes the fixed width expected, like the 1 of (f 1) for (f x:i32)
+    //   the integer out of range of the width is an error, but it's still the width so it isn't mismatched
+    fn fixed_literal(&mut self, node:&'t Node, expected:&Type) -> Option<Type> {
+        let Node::Literal{span, value} = node else {return None};
+        let Type::Fixed(suffix) = self.resolve(expected) else {return None};
+        match value {
+            Literal::Integer(a, NumberSuffix::None) => {
+                let (min, max) = suffix.integer_range()?;
+                let text = self.name(*a);
+                if !matches!(text.parse::<i128>(), Ok(v) if min <= v && v <= max) {
+                    let describe = format!("number '{}' out of range for '{}' ({}..={})", text, suffix.name(), min, max);
+                    self.diag.push_error(*span, &describe);
+                }
+            }
+            Literal::Float(_, NumberSuffix::None) if suffix.is_float() => {}
+            _ => {return None}
+        }
+        let ty = Type::Fixed(suffix);
+        self.types.insert(node.id(), ty.clone());
+        Some(ty)
+    }
+
+    fn expr(&mut self, node:&'t Node) -> Type {
+        let ty = self.synth(node);
+        self.types.insert(node.id(), ty.clone());
+        ty
+    }
+
+    fn synth(&mut self, node:&'t Node) -> Type {
+        match node {
+            Node::Literal{value, ..} => {
+                match value {
+                    Literal::Integer(_, suffix) => {Type::of_number(*suffix, false)}
+                    Literal::Float(_, suffix) => {Type::of_number(*suffix, true)}
+                    Literal::String(_) => {Type::String}
+                    Literal::Char(_) => {Type::Char}
+                    Literal::Bool(_) => {Type::Bool}
+                    Literal::Nil => {Type::Nil}
+                }
+            }
+            Node::Ident{..} => {
+                match self.resolution.binding_of(node) {
+                    Some(Binding::Global(define, _)) => {self.global(define)}
+                    Some(Binding::Param(define, _) | Binding::Local(define, _)) => {
+                        self.locals.get(&define).cloned().unwrap_or(Type::Any)
+                    }
+                    Some(Binding::Builtin) | None => {Type::Any}
+                }
+            }
+            Node::Call{callee, args, ..} => {self.call(node, callee, args)}
+            Node::MacroCall{args, ..} => {
+                self.exprs(args);
+                Type::Nil
+            }
+            Node::Block{items, ..} => {
+                let types = self.exprs(items);
+                types.last().cloned().unwrap_or(Type::Nil)
+            }
+            Node::List{span, items} => {
+                let types = self.exprs(items);
+                let Some(first) = types.first() else {
+                    let what = String::from("the item of empty list");
+                    return Type::List(Box::new(self.fresh(*span, what, false)))
+                };
+                // the items in inference are same type, or it's the least type of all
+                let mut item = self.resolve(first);
+                for a in &types[1..] {
+                    let a = self.resolve(a);
+                    if matches!(item, Type::Var(_)) || matches!(a, Type::Var(_)) {
+                        self.constrain(&a, &item);
+                    } else {
+                        item = item.join(&a);
+                    }
+                }
+                Type::List(Box::new(item))
+            }
+            Node::Format{parts, ..} => {
+                self.exprs(parts);
+                Type::String
+            }
+            Node::MemberAccess{span, object, member} => {
+                let ty = self.expr(object);
+                let ty = self.resolve(&ty);
+                match &ty {
+                    Type::Any | Type::Var(_) => {Type::Any}
+                    _ => {
+                        if let Some(a) = ty.slot_of(*member) {
+                            return a.clone()
+                        }
+                        let describe = format!("type {} has no slot '{}'", self.show(&ty), self.name(*member));
+                        self.diag.push_error(*span, &describe);
+                        Type::Any
+                    }
+                }
+            }
+            Node::ObjectLiteral{parent, slots, ..} => {self.object(parent, slots)}
+            Node::Assign{name, ty, value, ..} => {
+                let ty = self.binding(*name, ty, value);
+                self.locals.insert(node.id(), ty);
+                Type::Nil
+            }
+            Node::Rebind{target, value, ..} => {
+                let expected = self.expr(target);
+                let note = match target.as_ref() {
+                    Node::Ident{name, ..} => {
+                        let define = self.resolution.binding_of(target).and_then(|a| a.span());
+                        define.map(|a| (a, format!("'{}' is defined here as {}", self.name(*name), self.show(&expected))))
+                    }
+                    _ => {None}
+                };
+                self.check(value, &expected, note.as_ref().map(|(a, b)| (*a, b.as_str())));
+                Type::Nil
+            }
+            Node::Define{..} => {
+                self.function(node);
+                Type::Nil
+            }
+            _ => {
+                for a in node.children() {
+                    self.expr(a);
+                }
+                Type::Any
+            }
+        }
+    }
+
+    fn exprs(&mut self, nodes:&'t [Node]) -> Vec<Type> {
+        nodes.iter().map(|a| self.expr(a)).collect()
+    }
+
+    fn call(&mut self, node:&'t Node, callee:&'t Node, args:&'t [Node]) -> Type {
+        let binding = match callee {
+            Node::Ident{..} => {self.resolution.binding_of(callee)}
+            _ => {None}
+        };
+        if let (Some(Binding::Builtin), Node::Ident{name, ..}) = (binding, callee) {
+            self.expr(callee);
+            return self.builtin(node, &self.name(*name), args)
+        }
+        let ty = self.expr(callee);
+        match self.resolve(&ty) {
+            // the unknown callee is a function of the arguments
+            Type::Var(var) => {
+                let params = self.exprs(args);
+                let what = format!("the result of calling {}", callee.emit(self.pool));
+                let ret = self.fresh(node.span(), what, false);
+                self.solve(var, Type::Function(params, Box::new(ret.clone())));
+                ret
+            }
+            Type::Function(params, ret) => {
+                if params.len() != args.len() {
+                    let describe = format!("expects {} argument(s) but found {}", params.len(), args.len());
+                    self.diag.push_error(node.span(), &describe);
+                    self.exprs(args);
+                    return *ret
+                }
+                // the params of global function are shown in notes
+                let spans:Vec<Span> = match binding.and_then(|a| a.id()).and_then(|a| self.items.get(&a)) {
+                    Some(Node::Define{params, ..}) => {params.iter().map(|a| a.span()).collect()}
+                    _ => {Vec::new()}
+                };
+                for (i, (arg, param)) in args.iter().zip(&params).enumerate() {
+                    let note = spans.get(i).map(|a| (*a, "the parameter is declared here"));
+                    self.check(arg, param, note);
+                }
+                *ret
+            }
+            Type::Dispatch(name) => {
+                match binding {
+                    Some(Binding::Global(group, _)) => {self.dispatch(node, group, name, args)}
+                    _ => {self.exprs(args); Type::Any}
+                }
+            }
+            Type::Any => {
+                self.exprs(args);
+                Type::Any
+            }
+            ty => {
+                let describe = format!("can't call {}, it's not a function", self.show(&ty));
+                self.diag.push_error(callee.span(), &describe);
+                self.exprs(args);
+                Type::Any
+            }
+        }
+    }
+
+    // the call of group is bound to a method if it's sure, or it's dispatched when run
+    fn dispatch(&mut self, node:&'t Node, group:NodeId<'t>, name:Id, args:&'t [Node]) -> Type {
+        let types = self.exprs(args);
+        let types:Vec<Type> = types.iter().map(|a| self.resolve(a)).collect();
+        let methods = self.groups.get(&group).cloned().unwrap_or_default();
+        match dispatch_v1::select(&methods, &types) {
+            Selection::Static(i) => {
+                self.calls.insert(node.id(), methods[i].id);
+                self.resolve(&methods[i].ret)
+            }
+            Selection::Runtime(possible) => {
+                let rets:Vec<Type> = possible.iter().map(|i| self.resolve(&methods[*i].ret)).collect();
+                rets.iter().skip(1).fold(rets[0].clone(), |a, b| a.join(b))
+            }
+            Selection::None => {
+                let describe = format!("no method of '{}' is applicable to {}", self.name(name), dispatch_v1::show(&types, self.pool));
+                let error = self.diag.push_error(node.span(), &describe);
+                for a in &methods {
+                    error.note(a.span, &format!("a candidate is {}", dispatch_v1::show(&a.params, self.pool)));
+                }
+                Type::Any
+            }
+        }
+    }
+
+    // the builtins of std are variadic, so they're checked by rules here
+    //   the unknown argument is inferred as Number for arithmetic, and Bool for logic
+    fn builtin(&mut self, node:&'t Node, name:&str, args:&'t [Node]) -> Type {
+        let types = self.exprs(args);
+        let types:Vec<Type> = types.iter().map(|a| self.resolve(a)).collect();
+        match name {
+            "+" if matches!(types.first(), Some(Type::String)) => {Type::String}
+            "+" | "-" | "*" | "/" => {
+                for (arg, ty) in args.iter().zip(&types) {
+                    if !self.constrain(ty, &Type::Number) {
+                        self.mismatch(arg.span(), &Type::Number, ty);
+                    }
+                }
+                let types:Vec<Type> = types.iter().map(|a| self.resolve(a)).collect();
+                // the Integer with Float is Float when run
+                match types.iter().skip(1).fold(types.first().cloned().unwrap_or(Type::Any), |a, b| a.join(b)) {
+                    ty @ (Type::Any | Type::Float | Type::Fixed(_)) => {ty}
+                    _ if types.iter().any(|a| a.is_float()) => {Type::Float}
+                    ty if ty.is_numeric() => {ty}
+                    _ => {Type::Any}
+                }
+            }
+            "<" | ">" | "<=" | ">=" => {
+                for pair in types.windows(2) {
+                    let ok = match (&pair[0], &pair[1]) {
+                        (Type::Any, _) | (_, Type::Any) | (Type::Var(_), Type::Var(_)) => {true}
+                        (Type::Var(_), a) | (a, Type::Var(_)) => {
+                            let expected = if a.is_numeric() {Type::Number} else {a.clone()};
+                            self.constrain(&pair[0], &expected) && self.constrain(&pair[1], &expected)
+                        }
+                        (a, b) if a.is_numeric() && b.is_numeric() => {true}
+                        (a, b) => {a == b && matches!(a, Type::String | Type::Char)}
+                    };
+                    if !ok {
+                        let describe = format!("can't compare {} and {}", self.show(&pair[0]), self.show(&pair[1]));
+                        self.diag.push_error(node.span(), &describe);
+                        break
+                    }
+                }
+                Type::Bool
+            }
+            "not" | "and" | "or" => {
+                if name == "not" && args.len() != 1 {
+                    let describe = format!("expects 1 argument(s) but found {}", args.len());
+                    self.diag.push_error(node.span(), &describe);
+                }
+                for (arg, ty) in args.iter().zip(&types) {
+                    if !self.constrain(ty, &Type::Bool) {
+                        self.mismatch(arg.span(), &Type::Bool, ty);
+                    }
+                }
+                Type::Bool
+            }
+            "==" => {Type::Bool}
+            "each" => {Type::Nil}
+            _ => {Type::Any}
+        }
+    }
+
+    // the parent should be an object, the own slots are over the slots of parent
+    fn object(&mut self, parent:&'t Option<Box<Node>>, slots:&'t [Node]) -> Type {
+        let (mut protos, mut flat) = (Vec::new(), Vec::new());
+        if let Some(parent) = parent {
+            match self.expr(parent) {
+                Type::Object{protos: a, slots: b} => {protos = a; flat = b}
+                Type::Any => {}
+                ty => {
+                    let describe = format!("the parent of object should be an object but found {}", self.show(&ty));
+                    self.diag.push_error(parent.span(), &describe);
+                }
+            }
+        }
+        for slot in slots {
+            let Node::Slot{name, value, ..} = slot else {continue};
+            let ty = self.expr(value);
+            // the slot of parent is kept it's type, so the derived object can be used as the parent
+            match flat.iter().position(|(a, _)| a == name) {
+                Some(i) if !ty.is_subtype(&flat[i].1) => {
+                    let expected = flat[i].1.clone();
+                    self.mismatch(value.span(), &expected, &ty);
+                }
+                Some(i) => {flat[i].1 = ty}
+                None => {flat.push((*name, ty))}
+            }
+        }
+        Type::Object{protos, slots: flat}
+    }
+}
@@ -0,0 +1,147 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use farvm_utils::{Diag, Emit, Pool, Span};
+use farvm_tree::NodeId;
+use super::types_v1::Type;
+
+// The method of dispatch group by the id of it's Define, the ret is Any until it's body is checked
+#[derive(Clone,Debug)]
+pub struct Method<'t> {
+    pub id: NodeId<'t>,
+    pub span: Span,
+    pub params: Vec<Type>,
+    pub ret: Type,
+}
+
+impl<'t> Method<'t> {
+    // self is chosen before other when both are applicable
+    fn is_more_specific(&self, other:&Method) -> bool {
+        self.params.len() == other.params.len()
+            && self.params.iter().zip(&other.params).all(|(a, b)| a.is_within(b))
+            && !other.params.iter().zip(&self.params).all(|(a, b)| a.is_within(b))
+    }
+    fn is_same(&self, other:&Method) -> bool {
+        self.params.len() == other.params.len()
+            && self.params.iter().zip(&other.params).all(|(a, b)| a.is_within(b) && b.is_within(a))
+    }
+    // the widths of number aren't kept at runtime, so (i32) and (i64) are same when the method is chosen by values
+    fn is_same_at_runtime(&self, other:&Method) -> bool {
+        self.params.len() == other.params.len()
+            && self.params.iter().zip(&other.params).all(|(a, b)| match (a, b) {
+                (Type::Fixed(x), Type::Fixed(y)) => {x.is_float() == y.is_float()}
+                (Type::Fixed(x), Type::Float) | (Type::Float, Type::Fixed(x)) => {x.is_float()}
+                _ => {a.is_within(b) && b.is_within(a)}
+            })
+    }
+}
+
+// The method for the call found at compile time
+pub enum Selection {
+    Static(usize),                  // the method is the most specific for all values of arguments
+    Runtime(Vec<usize>),            // the methods maybe applicable, it's chosen by the values when run
+    None,
+}
+
+pub fn show(types:&[Type], pool:&Pool) -> String {
+    let types:Vec<String> = types.iter().map(|a| a.emit(pool)).collect();
+    format!("({})", types.join(" "))
+}
+
+// check the methods of group, then they're sorted by specificity, the more specific is before
+//   the methods with same parameter types are unreachable, and two methods are ambiguous if
+//   they're not more specific than each other, but a value can be both and no method is for it
+//   and the methods differ only in the widths of number are ambiguous, the first is always chosen at runtime
+pub fn check_group<'t>(name:&str, variadic:bool, methods:Vec<Method<'t>>, pool:&Pool, diag:&mut Diag) -> Vec<Method<'t>> {
+    let arity = methods.first().map_or(0, |a| a.params.len());
+    for a in &methods {
+        if !variadic && a.params.len() != arity {
+            let describe = format!("method of '{}' has {} parameter(s) but the first has {}, the group needs '...' for it",
+                name, a.params.len(), arity);
+            diag.push_error(a.span, &describe).note(methods[0].span, "the first method is here");
+        }
+    }
+    let mut reachable = Vec::new();
+    for (i, a) in methods.iter().enumerate() {
+        if let Some(before) = methods[..i].iter().find(|b| b.is_same(a)) {
+            let describe = format!("method of '{}' is unreachable, the method before has the same {}", name, show(&a.params, pool));
+            diag.push_error(a.span, &describe).note(before.span, "the method before is here");
+        } else {
+            reachable.push(a);
+        }
+    }
+    for (i, a) in reachable.iter().enumerate() {
+        for b in &reachable[i + 1..] {
+            if a.params.len() != b.params.len() || a.is_same(b) {
+                continue
+            }
+            if a.is_same_at_runtime(b) {
+                let describe = format!("methods of '{}' are ambiguous at runtime, {} and {} differ only in the widths of number",
+                    name, show(&a.params, pool), show(&b.params, pool));
+                diag.push_error(b.span, &describe).note(a.span, "the other method is here");
+                continue
+            }
+            if a.is_more_specific(b) || b.is_more_specific(a) {
+                continue
+            }
+            if !a.params.iter().zip(&b.params).all(|(x, y)| x.intersects(y)) {
+                continue
+            }
+            let meet:Vec<Type> = a.params.iter().zip(&b.params)
+                .map(|(x, y)| if x.is_within(y) {x.clone()} else {y.clone()})
+                .collect();
+            let meet = Method { id: a.id, span: a.span, params: meet, ret: Type::Any };
+            if methods.iter().any(|c| c.is_same(&meet)) {
+                continue
+            }
+            let describe = format!("methods of '{}' are ambiguous for {}, a method for it is needed", name, show(&meet.params, pool));
+            diag.push_error(b.span, &describe).note(a.span, "the other method is here");
+        }
+    }
+    // the topological order, a method is after all methods more specific than it
+    let mut rest = methods;
+    let mut sorted = Vec::new();
+    while !rest.is_empty() {
+        let i = (0..rest.len()).find(|i| !rest.iter().any(|b| b.is_more_specific(&rest[*i]))).unwrap_or(0);
+        sorted.push(rest.remove(i));
+    }
+    sorted
+}
+
+// the method for the types of arguments, the methods are sorted
+//   it's static if a method is sure applicable, and it's more specific than all others maybe applicable
+pub fn select(methods:&[Method], args:&[Type]) -> Selection {
+    let possible:Vec<usize> = (0..methods.len()).filter(|i| {
+        let params = &methods[*i].params;
+        params.len() == args.len() && args.iter().zip(params).all(|(a, p)| a.intersects(p))
+    }).collect();
+    if possible.is_empty() {
+        return Selection::None
+    }
+    let sure = possible.iter().copied().find(|i| args.iter().zip(&methods[*i].params).all(|(a, p)| a.is_within(p)));
+    match sure {
+        Some(i) if possible.iter().all(|j| *j == i || methods[i].is_more_specific(&methods[*j])) => {
+            Selection::Static(i)
+        }
+        _ => {Selection::Runtime(possible)}
+    }
+}
@@ -21,8 +21,39 @@
 // SOFTWARE.
 
 use farvm_tree::Node;
+use std::path::PathBuf;
+use farvm_utils::{Diag, Pool, Source};
 use farvm_vm::Program;
 
-pub fn build(tree:&Node) -> Program {
-    Program::new()
+mod lower_v1;
+mod module_v1;
+mod resolve_v1;
+mod types_v1;
+mod check_v1;
+mod dispatch_v1;
+pub use resolve_v1::{Binding, Resolution, Scope};
+pub use types_v1::Type;
+pub use check_v1::Typing;
+pub use dispatch_v1::Method;
+
+// the tree should be expanded, the errors are reported to diag
+pub fn build(tree:&Node, pool:&Pool, diag:&mut Diag) -> Program {
+    let resolution = resolve(tree, pool, diag);
+    let typing = check(tree, &resolution, pool, diag);
+    lower_v1::pass(tree, &typing, pool, diag)
+}
+
+// bind the names of linked tree to the locals, parameters, globals and builtins of std
+pub fn resolve<'t>(tree:&'t Node, pool:&Pool, diag:&mut Diag) -> Resolution<'t> {
+    resolve_v1::pass(tree, pool, diag)
+}
+
+// the files and the modules imported by them are linked to one tree, the first path is the entry
+pub fn load(paths:&[String], search:&[PathBuf], source:&mut Source, pool:&mut Pool, diag:&mut Diag) -> Result<Node, String> {
+    module_v1::load(paths, search, source, pool, diag)
+}
+
+// the types of resolved tree, the mismatched are reported to diag
+pub fn check<'t>(tree:&'t Node, resolution:&Resolution<'t>, pool:&Pool, diag:&mut Diag) -> Typing<'t> {
+    check_v1::pass(tree, resolution, pool, diag)
 }
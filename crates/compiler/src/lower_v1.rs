@@ -0,0 +1,358 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+use farvm_tree::{Literal, Node, NodeId};
+use farvm_utils::{Diag, Pool};
+use farvm_utils::pool::Id;
+use farvm_vm::{Function, Group, Instruction, Method, Program, TypeTest, Value};
+use super::check_v1::Typing;
+use super::dispatch_v1;
+use super::types_v1::Type;
+
+// The function in building, the locals are in scopes of blocks and never reused
+struct Builder {
+    function: Function,
+    scopes: Vec<Vec<(Id, usize)>>,
+}
+
+impl Builder {
+    fn new(name:String, params:&[Id]) -> Builder {
+        let scope = params.iter().enumerate().map(|(i, a)| (*a, i)).collect();
+        let function = Function { name, arity: params.len(), locals: params.len(), code: Vec::new() };
+        Builder { function, scopes: vec![scope] }
+    }
+    fn emit(&mut self, instruction:Instruction) {
+        self.function.code.push(instruction)
+    }
+    fn local_of(&self, name:Id) -> Option<usize> {
+        self.scopes.iter().rev().find_map(|scope| scope.iter().rev().find(|(a, _)| *a == name).map(|(_, i)| *i))
+    }
+    fn bind(&mut self, name:Id) -> usize {
+        let i = self.function.locals;
+        self.function.locals += 1;
+        self.scopes.last_mut().unwrap().push((name, i));
+        i
+    }
+}
+
+struct Object<'a, 't> {
+    pool: &'a Pool,
+    diag: &'a mut Diag,
+    typing: &'a Typing<'t>,
+    program: Program,
+    globals: HashMap<Id, usize>,
+    functions: HashMap<NodeId<'t>, usize>,     // the Define and methods of top level by id
+    groups: HashMap<Id, usize>,            // the DispatchGroup of top level by name
+}
+
+// the globals are the Define, DispatchGroup and Assign of top level, the init function set them in order
+//   all functions are set before the Assign, so the Assign can call any of them
+//   the functions are numbered before compiled, so a call bound to method at compile time is direct
+pub fn pass<'t>(tree:&'t Node, typing:&Typing<'t>, pool:&Pool, diag:&mut Diag) -> Program {
+    let mut me = Object { pool, diag, typing, program: Program::new(), globals: HashMap::new(), functions: HashMap::new(), groups: HashMap::new() };
+    let items = match tree {
+        Node::Module{items, ..} => {items.as_slice()}
+        _ => {std::slice::from_ref(tree)}
+    };
+    me.program.functions.push(Function::default());
+    for item in items {
+        if let Node::Define{name, ..} | Node::Assign{name, ..} | Node::DispatchGroup{name, ..} = item {
+            me.global(*name);
+        }
+        let functions:Vec<&Node> = match item {
+            Node::Define{..} => {vec![item]}
+            Node::DispatchGroup{methods, ..} => {methods.iter().collect()}
+            _ => {Vec::new()}
+        };
+        for a in functions {
+            me.functions.insert(a.id(), me.program.functions.len());
+            me.program.functions.push(Function::default());
+        }
+    }
+    let mut init = Builder::new(String::from("<init>"), &[]);
+    for item in items {
+        match item {
+            Node::Define{name, ..} => {
+                let function = me.functions[&item.id()];
+                me.define(item, function, me.pool.str_at(*name));
+                init.emit(Instruction::Push(Value::Function(function)));
+                init.emit(Instruction::StoreGlobal(me.globals[name]));
+            }
+            Node::DispatchGroup{name, methods, ..} => {
+                let group = me.group(item, *name, methods);
+                me.groups.insert(*name, group);
+                init.emit(Instruction::Push(Value::Dispatch(group)));
+                init.emit(Instruction::StoreGlobal(me.globals[name]));
+            }
+            _ => {}
+        }
+    }
+    for item in items {
+        match item {
+            Node::Define{..} | Node::DispatchGroup{..} => {}
+            Node::Assign{name, value, ..} => {
+                me.expr(&mut init, value);
+                init.emit(Instruction::StoreGlobal(me.globals[name]));
+            }
+            _ => {
+                me.expr(&mut init, item);
+                init.emit(Instruction::Pop);
+            }
+        }
+    }
+    init.emit(Instruction::Push(Value::Nil));
+    init.emit(Instruction::Return);
+    me.program.functions[0] = init.function;
+    me.program
+}
+
+impl<'a, 't> Object<'a, 't> {
+    fn global(&mut self, name:Id) -> usize {
+        if let Some(i) = self.globals.get(&name) {
+            return *i
+        }
+        let i = self.program.globals.len();
+        self.program.globals.push(self.pool.str_at(name));
+        self.globals.insert(name, i);
+        i
+    }
+
+    // the function is compiled to the index numbered before
+    fn define(&mut self, node:&'t Node, function:usize, name:String) {
+        let Node::Define{params, body, ..} = node else {
+            return
+        };
+        let params:Vec<Id> = params.iter().filter_map(|a| match a {
+            Node::Param{name, ..} => {Some(*name)}
+            _ => {None}
+        }).collect();
+        let mut builder = Builder::new(name, &params);
+        self.expr(&mut builder, body);
+        builder.emit(Instruction::Return);
+        self.program.functions[function] = builder.function;
+    }
+
+    // the methods are in the order of specificity from typing, the function of method is named like "md(Number)"
+    fn group(&mut self, node:&'t Node, name:Id, methods:&'t [Node]) -> usize {
+        let mut group = Group { name: self.pool.str_at(name), methods: Vec::new() };
+        for a in self.typing.methods_of(node) {
+            let (Some(method), Some(&function)) = (methods.iter().find(|b| b.id() == a.id), self.functions.get(&a.id)) else {
+                continue
+            };
+            let name = group.name.clone() + &dispatch_v1::show(&a.params, self.pool);
+            self.define(method, function, name);
+            let params = a.params.iter().map(|b| self.test_of(b)).collect();
+            group.methods.push(Method { params, function });
+        }
+        self.program.groups.push(group);
+        self.program.groups.len() - 1
+    }
+
+    fn test_of(&self, a:&Type) -> TypeTest {
+        match a {
+            Type::Nil => {TypeTest::Nil}
+            Type::Bool => {TypeTest::Bool}
+            Type::Char => {TypeTest::Char}
+            Type::String => {TypeTest::String}
+            Type::Number => {TypeTest::Number}
+            Type::Float => {TypeTest::Float}
+            Type::Fixed(a) if a.is_float() => {TypeTest::Float}
+            Type::Fixed(_) => {TypeTest::Integer}
+            Type::List(_) => {TypeTest::List}
+            Type::Function(..) | Type::Dispatch(_) => {TypeTest::Function}
+            Type::Object{protos, ..} => {
+                match protos.first().and_then(|a| self.globals.get(a)) {
+                    Some(global) => {TypeTest::Proto(*global)}
+                    None => {TypeTest::Object}
+                }
+            }
+            Type::Any | Type::Var(_) => {TypeTest::Any}
+        }
+    }
+
+    fn error(&mut self, node:&Node, describe:&str) {
+        self.diag.push_error(node.span(), describe);
+    }
+
+    // the code push one value of node
+    fn expr(&mut self, f:&mut Builder, node:&'t Node) {
+        match node {
+            Node::Literal{value, ..} => {
+                let value = self.literal(node, value);
+                f.emit(Instruction::Push(value));
+            }
+            Node::Ident{name, ..} => {self.load(f, *name)}
+            Node::Call{callee, args, ..} => {
+                // the call bound to a method at compile time is direct, or it's dispatched by the table of group
+                if let Some(function) = self.typing.method_of(node).and_then(|a| self.functions.get(&a)) {
+                    f.emit(Instruction::Push(Value::Function(*function)));
+                } else if let Some(group) = self.group_of(f, callee) {
+                    self.exprs(f, args);
+                    f.emit(Instruction::Dispatch { group, argc: args.len() });
+                    return
+                } else {
+                    self.expr(f, callee);
+                }
+                self.exprs(f, args);
+                f.emit(Instruction::Call(args.len()));
+            }
+            Node::MacroCall{name, args, ..} => {
+                f.emit(Instruction::LoadBuiltin(self.pool.str_at(*name) + "!"));
+                self.exprs(f, args);
+                f.emit(Instruction::Call(args.len()));
+            }
+            Node::Block{items, ..} => {
+                f.scopes.push(Vec::new());
+                for (i, item) in items.iter().enumerate() {
+                    self.expr(f, item);
+                    if i + 1 < items.len() {
+                        f.emit(Instruction::Pop);
+                    }
+                }
+                if items.is_empty() {
+                    f.emit(Instruction::Push(Value::Nil));
+                }
+                f.scopes.pop();
+            }
+            Node::List{items, ..} => {
+                self.exprs(f, items);
+                f.emit(Instruction::MakeList(items.len()));
+            }
+            Node::Format{parts, ..} => {
+                self.exprs(f, parts);
+                f.emit(Instruction::Format(parts.len()));
+            }
+            Node::MemberAccess{object, member, ..} => {
+                self.expr(f, object);
+                f.emit(Instruction::GetSlot(self.pool.str_at(*member)));
+            }
+            Node::ObjectLiteral{parent, slots, ..} => {self.object(f, parent, slots)}
+            Node::Assign{name, value, ..} => {
+                self.expr(f, value);
+                let i = f.bind(*name);
+                f.emit(Instruction::Store(i));
+                f.emit(Instruction::Push(Value::Nil));
+            }
+            Node::Rebind{target, value, ..} => {
+                self.rebind(f, target, value);
+                f.emit(Instruction::Push(Value::Nil));
+            }
+            Node::Define{name, ..} | Node::DispatchGroup{name, ..} => {
+                let describe = format!("'{}' should be defined at top level", self.pool.str_at(*name));
+                self.error(node, &describe);
+                f.emit(Instruction::Push(Value::Nil));
+            }
+            _ => {f.emit(Instruction::Push(Value::Nil))}
+        }
+    }
+
+    fn exprs(&mut self, f:&mut Builder, nodes:&'t [Node]) {
+        for a in nodes {
+            self.expr(f, a);
+        }
+    }
+
+    // the local, then global, or it's the builtin find when run
+    // the group if callee is the name of it and not a local
+    fn group_of(&self, f:&Builder, callee:&Node) -> Option<usize> {
+        match callee {
+            Node::Ident{name, ..} if f.local_of(*name).is_none() => {self.groups.get(name).copied()}
+            _ => {None}
+        }
+    }
+
+    fn load(&mut self, f:&mut Builder, name:Id) {
+        if let Some(i) = f.local_of(name) {
+            f.emit(Instruction::Load(i));
+        } else if let Some(i) = self.globals.get(&name) {
+            f.emit(Instruction::LoadGlobal(*i));
+        } else {
+            f.emit(Instruction::LoadBuiltin(self.pool.str_at(name)));
+        }
+    }
+
+    fn literal(&mut self, node:&Node, literal:&Literal) -> Value {
+        match literal {
+            Literal::Integer(a, _) => {
+                let text = self.pool.str_at(*a);
+                match text.parse::<i64>() {
+                    Ok(a) => {Value::Integer(a)}
+                    Err(_) => {
+                        self.error(node, &format!("integer {} is out of range of runtime Number", text));
+                        Value::Nil
+                    }
+                }
+            }
+            Literal::Float(a, _) => {Value::Float(self.pool.str_at(*a).parse().unwrap_or_default())}
+            Literal::String(a) => {Value::String(a.clone())}
+            Literal::Char(a) => {Value::Char(*a)}
+            Literal::Bool(a) => {Value::Bool(*a)}
+            Literal::Nil => {Value::Nil}
+        }
+    }
+
+    // the parent is pushed before the values of slots
+    fn object(&mut self, f:&mut Builder, parent:&'t Option<Box<Node>>, slots:&'t [Node]) {
+        if let Some(parent) = parent {
+            self.expr(f, parent);
+        }
+        let mut names:Vec<String> = Vec::new();
+        for slot in slots {
+            let Node::Slot{name, value, ..} = slot else {
+                continue
+            };
+            let name = self.pool.str_at(*name);
+            if names.contains(&name) {
+                self.error(slot, &format!("slot '{}' is defined again in object", name));
+            }
+            self.expr(f, value);
+            names.push(name);
+        }
+        f.emit(Instruction::MakeObject{slots: names, parent: parent.is_some()});
+    }
+
+    // the target is a local, global or slot of object
+    fn rebind(&mut self, f:&mut Builder, target:&'t Node, value:&'t Node) {
+        match target {
+            Node::Ident{name, ..} => {
+                self.expr(f, value);
+                if let Some(i) = f.local_of(*name) {
+                    f.emit(Instruction::Store(i));
+                } else if let Some(i) = self.globals.get(name) {
+                    f.emit(Instruction::StoreGlobal(*i));
+                } else {
+                    // the undefined is reported by resolve
+                    f.emit(Instruction::Pop);
+                }
+            }
+            Node::MemberAccess{object, member, ..} => {
+                self.expr(f, object);
+                self.expr(f, value);
+                f.emit(Instruction::SetSlot(self.pool.str_at(*member)));
+            }
+            _ => {
+                self.error(target, "can't rebind it, expect a name or a slot like obj.field");
+            }
+        }
+    }
+}
@@ -0,0 +1,406 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use farvm_tree::Node;
+use farvm_utils::{Diag, Pool, Source, Span};
+use farvm_utils::pool::Id;
+
+// The file of a module, the entry is the first file and it's globals are not prefixed
+//   the globals of other modules are prefixed by the module name, like "geo.shapes.area"
+struct Unit {
+    name: String,
+    prefix: String,
+    dir: PathBuf,
+    file: u32,
+    tree: Node,
+    imports: Vec<Edge>,
+    exports: HashMap<Id, (Span, bool)>,
+}
+
+// the import of a unit, it's resolved to the target unit
+struct Edge {
+    span: Span,
+    target: usize,
+    alias: Id,
+    names: Vec<Node>,
+}
+
+struct Object<'a> {
+    source: &'a mut Source,
+    pool: &'a mut Pool,
+    diag: &'a mut Diag,
+    search: &'a [PathBuf],
+    units: Vec<Unit>,
+    by_name: HashMap<String, usize>,
+    by_path: HashMap<PathBuf, usize>,
+}
+
+// load the files and the modules imported by them, then link all to one Module for compiler
+//   the first path is the entry, the modules are found in the dir of importer then the search paths
+//   the error of reading the given paths is returned, the others are reported to diag
+pub fn load(paths:&[String], search:&[PathBuf], source:&mut Source, pool:&mut Pool, diag:&mut Diag) -> Result<Node, String> {
+    let mut me = Object { source, pool, diag, search, units: Vec::new(), by_name: HashMap::new(), by_path: HashMap::new() };
+    for (i, path) in paths.iter().enumerate() {
+        let code = std::fs::read(path).map_err(|e| format!("{} for read '{}'", e, path))?;
+        let unit = me.add_unit(Path::new(path), code, i == 0);
+        let name = me.units[unit].name.clone();
+        me.register(unit, &name, None);
+    }
+    let mut i = 0;
+    while i < me.units.len() {
+        me.resolve_imports(i);
+        i += 1;
+    }
+    let order = me.check_cycles();
+    let mut items = Vec::new();
+    for i in order {
+        items.extend(me.link(i));
+    }
+    let span = me.units.first().map_or(Span::default(), |a| a.tree.span());
+    Ok(Node::Module{span, items})
+}
+
+impl<'a> Object<'a> {
+    fn add_unit(&mut self, path:&Path, code:Vec<u8>, entry:bool) -> usize {
+        let file = self.source.add_file(path.display().to_string(), code);
+        let tree = farvm_tree::build_v1(self.source.code(file), file, self.pool, self.diag);
+        let tree = farvm_tree::expand_v1(tree, self.pool, self.diag);
+        let declared = items_of(&tree).iter().find_map(|a| match a {
+            Node::ModuleDecl{path, ..} => {Some(self.pool.str_at(*path))}
+            _ => {None}
+        });
+        let stem = path.file_stem().map_or(String::new(), |a| a.to_string_lossy().into_owned());
+        let name = declared.unwrap_or(stem);
+        let prefix = if entry {String::new()} else {format!("{}.", name)};
+        let dir = match path.parent() {
+            Some(a) if !a.as_os_str().is_empty() => {a.to_path_buf()}
+            _ => {PathBuf::from(".")}
+        };
+        let exports = exports_of(&tree);
+        self.units.push(Unit { name, prefix, dir, file, tree, imports: Vec::new(), exports });
+        let unit = self.units.len() - 1;
+        if let Ok(path) = path.canonicalize() {
+            self.by_path.insert(path, unit);
+        }
+        unit
+    }
+
+    // the span of "module name" in the unit, or the start of file
+    fn decl_span(&self, unit:usize) -> Span {
+        let unit = &self.units[unit];
+        items_of(&unit.tree).iter().find_map(|a| match a {
+            Node::ModuleDecl{span, ..} => {Some(*span)}
+            _ => {None}
+        }).unwrap_or(Span::new(unit.file, 0, 0))
+    }
+
+    fn register(&mut self, unit:usize, name:&str, import:Option<Span>) {
+        if let Some(&before) = self.by_name.get(name) {
+            if before != unit {
+                let describe = format!("module '{}' is defined by two files", name);
+                let span = import.unwrap_or(self.decl_span(unit));
+                let note = self.decl_span(before);
+                self.diag.push_error(span, &describe).note(note, "the other file of it is here");
+            }
+            return
+        }
+        self.by_name.insert(String::from(name), unit);
+    }
+
+    // the file of module in dirs, like "geo/shapes.fa" for "geo.shapes"
+    fn find(&self, dir:&Path, name:&str) -> Option<PathBuf> {
+        let relative = name.replace('.', "/") + ".fa";
+        std::iter::once(dir).chain(self.search.iter().map(|a| a.as_path()))
+            .map(|a| a.join(&relative))
+            .find(|a| a.is_file())
+    }
+
+    fn resolve_imports(&mut self, unit:usize) {
+        let imports:Vec<(Span, Id, Option<Id>, Vec<Node>)> = items_of(&self.units[unit].tree).iter().filter_map(|a| match a {
+            Node::Import{span, path, alias, names} => {Some((*span, *path, *alias, names.clone()))}
+            _ => {None}
+        }).collect();
+        for (span, path, alias, names) in imports {
+            let name = self.pool.str_at(path);
+            let Some(target) = self.import_unit(unit, span, &name) else {
+                continue
+            };
+            let alias = alias.unwrap_or_else(|| {
+                let last = name.rsplit('.').next().unwrap_or(&name);
+                self.pool.add_str(last)
+            });
+            self.units[unit].imports.push(Edge { span, target, alias, names });
+        }
+    }
+
+    fn import_unit(&mut self, unit:usize, span:Span, name:&str) -> Option<usize> {
+        if let Some(&target) = self.by_name.get(name) {
+            return Some(target)
+        }
+        let Some(path) = self.find(&self.units[unit].dir.clone(), name) else {
+            let mut searched:Vec<String> = vec![self.units[unit].dir.display().to_string()];
+            searched.extend(self.search.iter().map(|a| a.display().to_string()));
+            let describe = format!("can't find module '{}' in: {}", name, searched.join(", "));
+            self.diag.push_error(span, &describe);
+            return None
+        };
+        let target = match path.canonicalize().ok().and_then(|a| self.by_path.get(&a).copied()) {
+            Some(target) => {target}
+            None => {
+                let code = match std::fs::read(&path) {
+                    Ok(code) => {code}
+                    Err(e) => {
+                        self.diag.push_error(span, &format!("{} for read '{}'", e, path.display()));
+                        return None
+                    }
+                };
+                self.add_unit(&path, code, false)
+            }
+        };
+        if self.units[target].name != name {
+            let describe = format!("module '{}' is imported, but the file declares module '{}'", name, self.units[target].name);
+            let note = self.decl_span(target);
+            self.diag.push_error(span, &describe).note(note, "it's declared here");
+            return None
+        }
+        self.register(target, name, Some(span));
+        Some(target)
+    }
+
+    // the order of units, the imported is before the importer, and the cycles are reported
+    fn check_cycles(&mut self) -> Vec<usize> {
+        let mut state = vec![0u8; self.units.len()];       // 0 unvisited, 1 in path, 2 done
+        let mut order = Vec::new();
+        let mut path:Vec<(usize, Span)> = Vec::new();
+        for unit in 0..self.units.len() {
+            self.visit(unit, &mut state, &mut path, &mut order);
+        }
+        order
+    }
+
+    fn visit(&mut self, unit:usize, state:&mut Vec<u8>, path:&mut Vec<(usize, Span)>, order:&mut Vec<usize>) {
+        if state[unit] != 0 {
+            return
+        }
+        state[unit] = 1;
+        let edges:Vec<(usize, Span)> = self.units[unit].imports.iter().map(|a| (a.target, a.span)).collect();
+        for (target, span) in edges {
+            if state[target] == 1 {
+                self.report_cycle(unit, target, span, path);
+                continue
+            }
+            path.push((unit, span));
+            self.visit(target, state, path, order);
+            path.pop();
+        }
+        state[unit] = 2;
+        order.push(unit);
+    }
+
+    // the cycle is from target in path to unit, and back to target by span
+    fn report_cycle(&mut self, unit:usize, target:usize, span:Span, path:&[(usize, Span)]) {
+        let from = path.iter().position(|(a, _)| *a == target).unwrap_or(path.len());
+        let mut names:Vec<&str> = path[from..].iter().map(|(a, _)| self.units[*a].name.as_str()).collect();
+        names.push(self.units[unit].name.as_str());
+        names.push(self.units[target].name.as_str());
+        let describe = format!("import cycle: {}", names.join(" -> "));
+        let error = self.diag.push_error(span, &describe);
+        for (k, (a, span)) in path.iter().enumerate().skip(from) {
+            let next = path.get(k + 1).map_or(unit, |(b, _)| *b);
+            error.note(*span, &format!("'{}' imports '{}' here", self.units[*a].name, self.units[next].name));
+        }
+    }
+
+    fn mangle(&mut self, unit:usize, name:Id) -> Id {
+        let prefix = &self.units[unit].prefix;
+        if prefix.is_empty() {
+            return name
+        }
+        let name = format!("{}{}", prefix, self.pool.str_at(name));
+        self.pool.add_str(name)
+    }
+
+    // the names can be used in unit, the own globals and the imported, the modules are by alias
+    fn env_of(&mut self, unit:usize) -> Env {
+        let mut env = Env { globals: HashMap::new(), modules: HashMap::new() };
+        let own:Vec<Id> = self.units[unit].exports.keys().copied().collect();
+        for name in own {
+            let mangled = self.mangle(unit, name);
+            env.globals.insert(name, mangled);
+        }
+        let imports:Vec<(Span, usize, Id, Vec<Node>)> = self.units[unit].imports.iter()
+            .map(|a| (a.span, a.target, a.alias, a.names.clone())).collect();
+        for (span, target, alias, names) in imports {
+            if let Some((before, _)) = self.units[unit].exports.get(&alias) {
+                let describe = format!("module alias '{}' is same as a global of this module", self.pool.str_at(alias));
+                let before = *before;
+                self.diag.push_error(span, &describe).note(before, "the global is defined here");
+            }
+            env.modules.insert(alias, target);
+            for a in &names {
+                let Node::Ident{span, name} = a else {continue};
+                let Some(mangled) = self.member_of(target, *name, *span) else {continue};
+                if let Some((before, _)) = self.units[unit].exports.get(name) {
+                    let describe = format!("'{}' is imported and also defined in this module", self.pool.str_at(*name));
+                    let before = *before;
+                    self.diag.push_error(*span, &describe).note(before, "it's defined here");
+                    continue
+                }
+                env.globals.insert(*name, mangled);
+            }
+        }
+        env
+    }
+
+    // the public global of module target, or report it's not found or private
+    fn member_of(&mut self, target:usize, name:Id, span:Span) -> Option<Id> {
+        match self.units[target].exports.get(&name).copied() {
+            Some((_, true)) => {Some(self.mangle(target, name))}
+            Some((define, false)) => {
+                let describe = format!("'{}' is private in module '{}'", self.pool.str_at(name), self.units[target].name);
+                self.diag.push_error(span, &describe).note(define, "it's defined here without 'pub'");
+                None
+            }
+            None => {
+                let describe = format!("module '{}' has no '{}'", self.units[target].name, self.pool.str_at(name));
+                self.diag.push_error(span, &describe);
+                None
+            }
+        }
+    }
+
+    // the items of unit with the names resolved to globals, the module, import and pub are removed
+    fn link(&mut self, unit:usize) -> Vec<Node> {
+        let env = self.env_of(unit);
+        let tree = std::mem::take(&mut self.units[unit].tree);
+        let Node::Module{items, ..} = tree else {
+            return Vec::new()
+        };
+        let mut linked = Vec::new();
+        for item in items {
+            let item = match item {
+                Node::ModuleDecl{..} | Node::Import{..} => {continue}
+                Node::Public{item, ..} => {*item}
+                _ => {item}
+            };
+            let item = match item {
+                Node::Define{span, name, generics, params, body} => {
+                    let name = env.globals.get(&name).copied().unwrap_or(name);
+                    Node::Define{span, name, generics, params, body}
+                }
+                Node::Assign{span, name, ty, value} => {
+                    let name = env.globals.get(&name).copied().unwrap_or(name);
+                    Node::Assign{span, name, ty, value}
+                }
+                Node::DispatchGroup{span, strategy, name, variadic, methods} => {
+                    let name = env.globals.get(&name).copied().unwrap_or(name);
+                    let methods = methods.into_iter().map(|a| match a {
+                        Node::Define{span, generics, params, body, ..} => {Node::Define{span, name, generics, params, body}}
+                        _ => {a}
+                    }).collect();
+                    Node::DispatchGroup{span, strategy, name, variadic, methods}
+                }
+                _ => {item}
+            };
+            let mut scopes = Vec::new();
+            linked.push(self.resolve(&env, &mut scopes, item));
+        }
+        linked
+    }
+
+    // the names not in scopes of locals are resolved by env
+    fn resolve(&mut self, env:&Env, scopes:&mut Vec<Vec<Id>>, node:Node) -> Node {
+        let is_local = |scopes:&Vec<Vec<Id>>, name:Id| scopes.iter().any(|a| a.contains(&name));
+        match node {
+            Node::Ident{span, name} if !is_local(scopes, name) => {
+                Node::Ident{span, name: env.globals.get(&name).copied().unwrap_or(name)}
+            }
+            Node::MemberAccess{span, object, member} => {
+                match *object {
+                    Node::Ident{name, ..} if !is_local(scopes, name) && env.modules.contains_key(&name) => {
+                        match self.member_of(env.modules[&name], member, span) {
+                            Some(name) => {Node::Ident{span, name}}
+                            None => {Node::Error{span}}
+                        }
+                    }
+                    object => {
+                        let object = self.resolve(env, scopes, object);
+                        Node::MemberAccess{span, object: Box::new(object), member}
+                    }
+                }
+            }
+            Node::Define{..} | Node::Block{..} => {
+                let mut scope = Vec::new();
+                if let Node::Define{params, ..} = &node {
+                    scope.extend(params.iter().filter_map(|a| match a {
+                        Node::Param{name, ..} => {Some(*name)}
+                        _ => {None}
+                    }));
+                }
+                scopes.push(scope);
+                let node = node.map_children(&mut |a| vec![self.resolve(env, scopes, a)]);
+                scopes.pop();
+                node
+            }
+            Node::Assign{..} => {
+                let node = node.map_children(&mut |a| vec![self.resolve(env, scopes, a)]);
+                if let (Node::Assign{name, ..}, Some(scope)) = (&node, scopes.last_mut()) {
+                    scope.push(*name);
+                }
+                node
+            }
+            _ => {node.map_children(&mut |a| vec![self.resolve(env, scopes, a)])}
+        }
+    }
+}
+
+struct Env {
+    globals: HashMap<Id, Id>,
+    modules: HashMap<Id, usize>,
+}
+
+fn items_of(tree:&Node) -> &[Node] {
+    match tree {
+        Node::Module{items, ..} => {items.as_slice()}
+        _ => {&[]}
+    }
+}
+
+// the globals of module, it's the span of define and it's public or not
+fn exports_of(tree:&Node) -> HashMap<Id, (Span, bool)> {
+    let mut exports = HashMap::new();
+    for item in items_of(tree) {
+        let (item, public) = match item {
+            Node::Public{item, ..} => {(item.as_ref(), true)}
+            _ => {(item, false)}
+        };
+        match item {
+            Node::Define{span, name, ..} | Node::Assign{span, name, ..} | Node::DispatchGroup{span, name, ..} => {
+                exports.entry(*name).or_insert((*span, public));
+            }
+            _ => {}
+        }
+    }
+    exports
+}
@@ -0,0 +1,289 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashMap;
+use farvm_tree::{Node, NodeId, Visitor, visit};
+use farvm_utils::{Diag, Pool, Span};
+use farvm_utils::pool::Id;
+
+// What a name is bound to, the id and span are of the Define, Param or Assign it's defined
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Binding<'t> {
+    Global(NodeId<'t>, Span),
+    Param(NodeId<'t>, Span),
+    Local(NodeId<'t>, Span),
+    Builtin,
+}
+
+impl<'t> Binding<'t> {
+    pub fn id(&self) -> Option<NodeId<'t>> {
+        match self {
+            Binding::Global(a, _) | Binding::Param(a, _) | Binding::Local(a, _) => {Some(*a)}
+            Binding::Builtin => {None}
+        }
+    }
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Binding::Global(_, a) | Binding::Param(_, a) | Binding::Local(_, a) => {Some(*a)}
+            Binding::Builtin => {None}
+        }
+    }
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Binding::Global(..) => {"global"}
+            Binding::Param(..) => {"parameter"}
+            Binding::Local(..) => {"local"}
+            Binding::Builtin => {"builtin"}
+        }
+    }
+}
+
+// The scope of module, function or block, the names are in order of defined
+//   the scope 0 is of module, it's the globals
+pub struct Scope<'t> {
+    pub parent: Option<usize>,
+    pub span: Span,
+    pub names: Vec<(Id, Binding<'t>)>,
+}
+
+// The scope tree, and the bindings of every Ident and MacroCall by the id of node
+pub struct Resolution<'t> {
+    pub scopes: Vec<Scope<'t>>,
+    bindings: HashMap<NodeId<'t>, Binding<'t>>,
+}
+
+impl<'t> Resolution<'t> {
+    pub fn binding_of(&self, node:&'t Node) -> Option<Binding<'t>> {
+        self.bindings.get(&node.id()).copied()
+    }
+}
+
+struct Object<'a, 't> {
+    pool: &'a Pool,
+    diag: &'a mut Diag,
+    builtins: Vec<&'static str>,
+    scopes: Vec<Scope<'t>>,
+    current: usize,
+    bindings: HashMap<NodeId<'t>, Binding<'t>>,
+}
+
+// bind the names of linked tree, the undefined and duplicate are errors, the shadowing are warnings
+//   the bindings are by the id of node, so the tree is borrowed until they're dropped
+//   the globals are defined before all, so functions can use the globals defined after them
+//   the locals are defined after it's Assign, so a block can't use the locals defined after
+pub fn pass<'t>(tree:&'t Node, pool:&Pool, diag:&mut Diag) -> Resolution<'t> {
+    let builtins = farvm_std::builtins().iter().map(|a| a.name).collect();
+    let scope = Scope { parent: None, span: tree.span(), names: Vec::new() };
+    let mut me = Object { pool, diag, builtins, scopes: vec![scope], current: 0, bindings: HashMap::new() };
+    let items = match tree {
+        Node::Module{items, ..} => {items.as_slice()}
+        _ => {std::slice::from_ref(tree)}
+    };
+    for item in items {
+        if let Node::Define{span, name, ..} | Node::DispatchGroup{span, name, ..} | Node::Assign{span, name, ..} = item {
+            me.define_global(*name, Binding::Global(item.id(), *span));
+        }
+    }
+    for item in items {
+        match item {
+            Node::Assign{value, ..} => {me.visit(value)}
+            _ => {me.visit(item)}
+        }
+    }
+    Resolution { scopes: me.scopes, bindings: me.bindings }
+}
+
+impl<'a, 't> Object<'a, 't> {
+    fn open(&mut self, span:Span) {
+        self.scopes.push(Scope { parent: Some(self.current), span, names: Vec::new() });
+        self.current = self.scopes.len() - 1;
+    }
+    fn close(&mut self) {
+        self.current = self.scopes[self.current].parent.unwrap_or(0);
+    }
+
+    // the scopes from current to module
+    fn chain(&self) -> impl Iterator<Item = &Scope<'t>> {
+        std::iter::successors(Some(&self.scopes[self.current]), |a| a.parent.map(|i| &self.scopes[i]))
+    }
+
+    fn lookup(&self, name:Id) -> Option<Binding<'t>> {
+        let found = self.chain().find_map(|scope| scope.names.iter().rev().find(|(a, _)| *a == name).map(|(_, b)| *b));
+        found.or_else(|| {
+            let name = self.pool.str_at(name);
+            self.builtins.contains(&name.as_str()).then_some(Binding::Builtin)
+        })
+    }
+
+    fn define_global(&mut self, name:Id, binding:Binding<'t>) {
+        if let Some((_, before)) = self.scopes[0].names.iter().find(|(a, _)| *a == name) {
+            let describe = format!("global '{}' is defined again", self.pool.str_at(name));
+            let before = before.span().unwrap_or_default();
+            self.diag.push_error(binding.span().unwrap_or_default(), &describe).note(before, "the first is here");
+            return
+        }
+        self.scopes[0].names.push((name, binding));
+    }
+
+    // the name is error if it's defined again in one scope, the shadowing of outer scopes are warnings
+    fn define(&mut self, name:Id, binding:Binding<'t>) {
+        let span = binding.span().unwrap_or_default();
+        let text = self.pool.str_at(name);
+        let again = self.scopes[self.current].names.iter().find(|(a, _)| *a == name).map(|(_, b)| *b);
+        match again {
+            Some(before) => {
+                let describe = format!("{} '{}' is defined again", binding.kind_name(), text);
+                self.diag.push_error(span, &describe).note(before.span().unwrap_or_default(), "the first is here");
+            }
+            // the names renamed by macro expansion are never same as the names of user
+            _ if text.contains('{') => {}
+            _ => {
+                if let Some(before) = self.lookup(name) {
+                    let describe = format!("'{}' shadows the {} defined before", text, before.kind_name());
+                    let warning = self.diag.push_warning(span, &describe);
+                    if let Some(before) = before.span() {
+                        warning.note(before, "it's defined here");
+                    }
+                }
+            }
+        }
+        self.scopes[self.current].names.push((name, binding));
+    }
+
+    fn bind(&mut self, node:&'t Node, binding:Binding<'t>) {
+        self.bindings.insert(node.id(), binding);
+    }
+
+    fn undefined(&mut self, span:Span, what:&str, name:&str, candidates:Vec<String>) {
+        let mut describe = format!("{} '{}' is not defined", what, name);
+        if let Some(similar) = similar_of(name, &candidates) {
+            describe += &format!(", did you mean '{}'?", similar);
+        }
+        self.diag.push_error(span, &describe);
+    }
+
+    // the names can be used here, the nearest are first
+    //   the names in Pool are after them, they're the names of other scopes and modules
+    fn visible_names(&self) -> Vec<String> {
+        let mut names:Vec<String> = self.chain()
+            .flat_map(|scope| scope.names.iter().rev().map(|(a, _)| self.pool.str_at(*a)))
+            .collect();
+        names.extend(self.builtins.iter().filter(|a| !a.ends_with('!')).map(|a| String::from(*a)));
+        names.extend(self.pool.strs().filter(|a| is_name(a)).map(String::from));
+        names
+    }
+}
+
+impl<'a, 't> Visitor<'t> for Object<'a, 't> {
+    fn visit_define(&mut self, node:&'t Node) {
+        let Node::Define{span, params, body, ..} = node else {return};
+        self.open(*span);
+        for param in params {
+            if let Node::Param{span, name, ..} = param {
+                self.define(*name, Binding::Param(param.id(), *span));
+            }
+        }
+        self.visit(body);
+        self.close();
+    }
+
+    fn visit_block(&mut self, node:&'t Node) {
+        self.open(node.span());
+        visit::walk(self, node);
+        self.close();
+    }
+
+    fn visit_assign(&mut self, node:&'t Node) {
+        let Node::Assign{span, name, value, ..} = node else {return};
+        self.visit(value);
+        self.define(*name, Binding::Local(node.id(), *span));
+    }
+
+    fn visit_ident(&mut self, node:&'t Node) {
+        let Node::Ident{span, name} = node else {return};
+        match self.lookup(*name) {
+            Some(binding) => {self.bind(node, binding)}
+            None => {
+                let candidates = self.visible_names();
+                self.undefined(*span, "name", &self.pool.str_at(*name), candidates);
+            }
+        }
+    }
+
+    fn visit_macro_call(&mut self, node:&'t Node) {
+        let Node::MacroCall{span, name, ..} = node else {return};
+        let text = self.pool.str_at(*name) + "!";
+        if self.builtins.contains(&text.as_str()) {
+            self.bind(node, Binding::Builtin);
+        } else {
+            let candidates = self.builtins.iter().filter(|a| a.ends_with('!')).map(|a| String::from(*a)).collect();
+            self.undefined(*span, "macro", &text, candidates);
+        }
+        visit::walk(self, node);
+    }
+
+    fn visit_rebind(&mut self, node:&'t Node) {
+        let Node::Rebind{target, value, ..} = node else {return};
+        if let Node::Ident{span, name} = target.as_ref() {
+            if self.lookup(*name) == Some(Binding::Builtin) {
+                let describe = format!("can't rebind the builtin '{}'", self.pool.str_at(*name));
+                self.diag.push_error(*span, &describe);
+            }
+        }
+        self.visit(target);
+        self.visit(value);
+    }
+}
+
+// the most similar name, it's not too far from name by the edit distance
+pub(crate) fn similar_of<'b>(name:&str, candidates:&'b [String]) -> Option<&'b str> {
+    let limit = (name.chars().count() + 1) / 3;
+    candidates.iter()
+        .filter(|a| !a.contains('{') && a.as_str() != name)
+        .map(|a| (distance(name, a), a.as_str()))
+        .filter(|(d, _)| *d <= limit)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, a)| a)
+}
+
+// the string of Pool is a name if it's like an identifier, not the path of module or text
+fn is_name(text:&str) -> bool {
+    let mut chars = text.chars();
+    chars.next().map(|a| a.is_alphabetic() || a == '_').unwrap_or(false)
+        && chars.all(|a| a.is_alphanumeric() || a == '_' || a == '-' || a == '?')
+}
+
+// the Levenshtein distance by chars
+fn distance(a:&str, b:&str) -> usize {
+    let b:Vec<char> = b.chars().collect();
+    let mut row:Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(diagonal + usize::from(x != *y));
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,193 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use farvm_tree::NumberSuffix;
+use farvm_utils::{Emit, Pool};
+use farvm_utils::pool::Id;
+
+// The static type, the lattice of subtype is
+//   Any
+//     Number          the integer without suffix is Number
+//       Float         f32 f64
+//       i8 i16 i32 i64 u8 u16 u32 u64
+//     Nil Bool Char String List<T> (-> T U) Object
+//   the Any is also the unknown, like the unannotated parameter, it's compatible with all types in both way
+#[derive(Clone,Debug,PartialEq)]
+pub enum Type {
+    Any,
+    Nil,
+    Bool,
+    Char,
+    String,
+    Number,
+    Float,
+    Fixed(NumberSuffix),
+    List(Box<Type>),
+    Function(Vec<Type>, Box<Type>),
+    // the protos are the globals of prototype derived from, the nearest is first
+    //   the slots are with the slots of parents, it's like the object is flat
+    Object{protos:Vec<Id>, slots:Vec<(Id, Type)>},
+    Dispatch(Id),                   // the dispatch group, the method is chosen by arguments
+    Var(usize),                     // the type in inference, it's like Any until solved
+}
+
+// the names can be written in annotation, the List is with a parameter
+pub const NAMES:&[&str] = &[
+    "Any", "Nil", "Bool", "Char", "String", "Number", "Float", "List",
+    "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64",
+];
+
+impl Type {
+    pub fn from_name(name:&str) -> Option<Type> {
+        match name {
+            "Any" => {Some(Type::Any)}
+            "Nil" => {Some(Type::Nil)}
+            "Bool" => {Some(Type::Bool)}
+            "Char" => {Some(Type::Char)}
+            "String" => {Some(Type::String)}
+            "Number" => {Some(Type::Number)}
+            "Float" => {Some(Type::Float)}
+            _ => {
+                match NumberSuffix::from_name(name) {
+                    Some(NumberSuffix::None) | None => {None}
+                    Some(suffix) => {Some(Type::Fixed(suffix))}
+                }
+            }
+        }
+    }
+
+    // the type of number literal by it's suffix
+    pub fn of_number(suffix:NumberSuffix, float:bool) -> Type {
+        match suffix {
+            NumberSuffix::None if float => {Type::Float}
+            NumberSuffix::None => {Type::Number}
+            _ => {Type::Fixed(suffix)}
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Number | Type::Float | Type::Fixed(_))
+    }
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Float) || matches!(self, Type::Fixed(a) if a.is_float())
+    }
+
+    pub fn contains_var(&self, var:usize) -> bool {
+        match self {
+            Type::Var(a) => {*a == var}
+            Type::List(a) => {a.contains_var(var)}
+            Type::Function(params, ret) => {params.iter().any(|a| a.contains_var(var)) || ret.contains_var(var)}
+            Type::Object{slots, ..} => {slots.iter().any(|(_, a)| a.contains_var(var))}
+            _ => {false}
+        }
+    }
+
+    pub fn slot_of(&self, name:Id) -> Option<&Type> {
+        match self {
+            Type::Object{slots, ..} => {slots.iter().find(|(a, _)| *a == name).map(|(_, a)| a)}
+            _ => {None}
+        }
+    }
+
+    // self can be used as other
+    pub fn is_subtype(&self, other:&Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) | (Type::Var(_), _) | (_, Type::Var(_)) => {true}
+            (Type::Float, Type::Number) | (Type::Fixed(_), Type::Number) => {true}
+            (Type::Fixed(a), Type::Float) => {a.is_float()}
+            (Type::List(a), Type::List(b)) => {a.is_subtype(b)}
+            (Type::Function(ps, r), Type::Function(qs, s)) => {
+                ps.len() == qs.len() && qs.iter().zip(ps).all(|(q, p)| q.is_subtype(p)) && r.is_subtype(s)
+            }
+            (Type::Object{protos: a, ..}, Type::Object{protos: b, slots}) => {
+                b.iter().take(1).all(|p| a.contains(p))
+                    && slots.iter().all(|(name, b)| matches!(self.slot_of(*name), Some(a) if a.is_subtype(b)))
+            }
+            (Type::Dispatch(_), Type::Function(..)) => {true}
+            _ => {self == other}
+        }
+    }
+
+    // self is under other in the lattice, it's for dispatch so the Any is only the top, not the unknown
+    pub fn is_within(&self, other:&Type) -> bool {
+        match (self, other) {
+            (_, Type::Any) => {true}
+            (Type::Any | Type::Var(_), _) | (_, Type::Var(_)) => {false}
+            (Type::List(a), Type::List(b)) => {a.is_within(b)}
+            _ => {self.is_subtype(other)}
+        }
+    }
+
+    // a value can be both, the unknown can be any
+    pub fn intersects(&self, other:&Type) -> bool {
+        matches!(self, Type::Any | Type::Var(_)) || self.is_within(other) || other.is_within(self)
+    }
+
+    // the least type both can be used as
+    pub fn join(&self, other:&Type) -> Type {
+        if self.is_subtype(other) && !matches!(self, Type::Any) {
+            return other.clone()
+        }
+        if other.is_subtype(self) {
+            return self.clone()
+        }
+        match (self, other) {
+            (a, b) if a.is_float() && b.is_float() => {Type::Float}
+            (a, b) if a.is_numeric() && b.is_numeric() => {Type::Number}
+            (Type::List(a), Type::List(b)) => {Type::List(Box::new(a.join(b)))}
+            (Type::Object{protos: a, slots}, Type::Object{protos: b, ..}) => {
+                let protos = a.iter().filter(|p| b.contains(p)).copied().collect();
+                let slots = slots.iter().filter_map(|(name, a)| other.slot_of(*name).map(|b| (*name, a.join(b)))).collect();
+                Type::Object{protos, slots}
+            }
+            _ => {Type::Any}
+        }
+    }
+}
+
+// like "Number", "List<i32>", "(-> Number Bool)", "point" and "{x: Number y: Number}"
+impl Emit for Type {
+    fn emit(&self, pool:&Pool) -> String {
+        match self {
+            Type::Any => {String::from("Any")}
+            Type::Nil => {String::from("Nil")}
+            Type::Bool => {String::from("Bool")}
+            Type::Char => {String::from("Char")}
+            Type::String => {String::from("String")}
+            Type::Number => {String::from("Number")}
+            Type::Float => {String::from("Float")}
+            Type::Fixed(a) => {String::from(a.name())}
+            Type::List(a) => {format!("List<{}>", a.emit(pool))}
+            Type::Function(params, ret) => {
+                let params:Vec<String> = params.iter().map(|a| a.emit(pool)).collect();
+                format!("({} -> {})", params.join(" "), ret.emit(pool))
+            }
+            Type::Object{protos, ..} if !protos.is_empty() => {pool.str_at(protos[0])}
+            Type::Object{slots, ..} => {
+                let slots:Vec<String> = slots.iter().map(|(name, a)| format!("{}: {}", pool.str_at(*name), a.emit(pool))).collect();
+                format!("{{{}}}", slots.join(" "))
+            }
+            Type::Dispatch(a) => {format!("dispatch {}", pool.str_at(*a))}
+            Type::Var(_) => {String::from("?")}
+        }
+    }
+}
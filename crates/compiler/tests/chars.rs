@@ -0,0 +1,41 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+#[test]
+fn char_value() {
+    assert_eq!(run("(main) = ['a' '😀' '\\u{41}']").unwrap(), "[a 😀 A]");
+}
+
+#[test]
+fn compare_chars() {
+    assert_eq!(run("(main) = [(< 'a' 'b') (>= 'a' 'b')]").unwrap(), "[true false]");
+}
+
+// the Char isn't a Number or a String
+#[test]
+fn char_type() {
+    assert_eq!(errors_of("(main) = (+ 'a' 1)"), vec!["mismatched types: expected Number but found Char"]);
+    assert_eq!(errors_of("(f c:Char) = c\n(main) = (f \"a\")"), vec!["mismatched types: expected Char but found String"]);
+}
@@ -0,0 +1,114 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+// The helpers of tests, every test file uses a part of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use farvm_tree::Node;
+use farvm_utils::{diag, pool, source, Diag, Pool};
+use farvm_utils::diag::DiagUnit;
+use farvm_vm::Program;
+
+pub fn describes(units:&[DiagUnit]) -> Vec<String> {
+    units.iter().map(|a| a.describe()).collect()
+}
+
+// the expanded tree of one file
+pub fn tree(code:&str) -> (Node, Pool, Diag) {
+    let mut the_source = source::make();
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let file = the_source.add_file("test.fa", code.as_bytes().to_vec());
+    let the_tree = farvm_tree::build_v1(the_source.code(file), file, &mut unique_pool, &mut the_diag);
+    let the_tree = farvm_tree::expand_v1(the_tree, &mut unique_pool, &mut the_diag);
+    (the_tree, unique_pool, the_diag)
+}
+
+pub fn resolve(code:&str) -> Diag {
+    let (the_tree, unique_pool, mut the_diag) = tree(code);
+    farvm_compiler::resolve(&the_tree, &unique_pool, &mut the_diag);
+    the_diag
+}
+
+pub fn check(code:&str) -> Diag {
+    let (the_tree, unique_pool, mut the_diag) = tree(code);
+    let resolution = farvm_compiler::resolve(&the_tree, &unique_pool, &mut the_diag);
+    farvm_compiler::check(&the_tree, &resolution, &unique_pool, &mut the_diag);
+    the_diag
+}
+
+pub fn build(code:&str) -> (Program, Diag) {
+    let (the_tree, unique_pool, mut the_diag) = tree(code);
+    let program = farvm_compiler::build(&the_tree, &unique_pool, &mut the_diag);
+    (program, the_diag)
+}
+
+// the errors of all phases until the program
+pub fn errors_of(code:&str) -> Vec<String> {
+    describes(build(code).1.errors())
+}
+
+// the result of "main" as text, the code should have no error
+pub fn run(code:&str) -> Result<String, String> {
+    let (program, the_diag) = build(code);
+    assert!(!the_diag.has_error(), "{:?}", describes(the_diag.errors()));
+    execute(&program)
+}
+
+pub fn execute(program:&Program) -> Result<String, String> {
+    let mut cpu = farvm_vm::make();
+    farvm_std::install(&mut cpu);
+    farvm_vm::run(&mut cpu, program).map(|a| a.to_string())
+}
+
+// the files are written to a new dir in temp, it's removed by the caller
+pub fn write(dir:&str, files:&[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("farvm-{}-{}", dir, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for (path, code) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, code).unwrap();
+    }
+    root
+}
+
+// the result of "main" of the files, the first is the entry, or the errors
+pub fn run_files(dir:&str, files:&[(&str, &str)]) -> Result<String, Vec<String>> {
+    let root = write(dir, files);
+    let mut the_source = source::make();
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let entry = vec![root.join(files[0].0).display().to_string()];
+    let the_tree = farvm_compiler::load(&entry, &[], &mut the_source, &mut unique_pool, &mut the_diag).unwrap();
+    let _ = fs::remove_dir_all(&root);
+    if the_diag.has_error() {
+        return Err(describes(the_diag.errors()))
+    }
+    let program = farvm_compiler::build(&the_tree, &unique_pool, &mut the_diag);
+    if the_diag.has_error() {
+        return Err(describes(the_diag.errors()))
+    }
+    execute(&program).map_err(|e| vec![e])
+}
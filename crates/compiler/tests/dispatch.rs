@@ -0,0 +1,128 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+const MD:&str = r#"
+for apply (md ...) {
+    (a:Number) = {"Number:{a}"}
+    (a:Float) = {"Float:{a}"}
+    (a:String) = {"String:{a}"}
+}
+"#;
+
+#[test]
+fn static_call_binds_the_most_specific() {
+    let code = String::from(MD) + "(main) = [(md 1) (md 2.5) (md \"s\")]";
+    assert_eq!(run(&code).unwrap(), "[Number:1 Float:2.5 String:s]");
+}
+
+// the calls expanded from one macro template have same span, they're bound one by one
+#[test]
+fn calls_from_one_macro_template() {
+    let code = String::from(MD) + "(call_md! x) = (md x)\n(main) = [(call_md! 1) (call_md! \"s\") (call_md! 2.5)]";
+    assert_eq!(run(&code).unwrap(), "[Number:1 String:s Float:2.5]");
+}
+
+#[test]
+fn ambiguous_methods() {
+    let errors = errors_of(r#"
+for apply (two ...) {
+    (a:Float b:Number) = {1}
+    (a:Number b:Float) = {2}
+}
+"#);
+    assert_eq!(errors, vec!["methods of 'two' are ambiguous for (Float Float), a method for it is needed"]);
+}
+
+#[test]
+fn unreachable_method() {
+    let errors = errors_of(r#"
+for apply (one ...) {
+    (a:Number) = {1}
+    (b:Number) = {2}
+}
+"#);
+    assert_eq!(errors, vec!["method of 'one' is unreachable, the method before has the same (Number)"]);
+}
+
+#[test]
+fn no_applicable_method_at_compile_time() {
+    let errors = errors_of(&(String::from(MD) + "(main) = (md true)"));
+    assert_eq!(errors, vec!["no method of 'md' is applicable to (Bool)"]);
+}
+
+// the README example, the pipe of group and function is called by each
+#[test]
+fn each_item_by_pipe() {
+    let code = String::from(MD) + r#"
+log = ""
+(note a) = {log <- (+ log a " ")}
+(main) = {
+    (each (pipe md note) [1 2.5 "s"])
+    log
+}
+"#;
+    assert_eq!(run(&code).unwrap(), "Number:1 Float:2.5 String:s ");
+}
+
+const SHAPE:&str = r#"
+Shape = @{area: 0}
+Circle = @Shape{area: 3}
+for apply (area ...) {
+    (a:Number) = {"Number:{a}"}
+    (a:Shape) = {"Shape:{a.area}"}
+}
+(show a) = (area a)
+"#;
+
+// the argument of show is Any, so the method is chosen when run
+#[test]
+fn runtime_dispatch_by_proto() {
+    let code = String::from(SHAPE) + "(main) = [(show 1) (show Circle) (show Shape)]";
+    assert_eq!(run(&code).unwrap(), "[Number:1 Shape:3 Shape:0]");
+}
+
+#[test]
+fn no_applicable_method_at_runtime() {
+    let code = String::from(SHAPE) + "(main) = (show true)";
+    assert_eq!(run(&code).unwrap_err(), "no method of 'area' is applicable to (Bool), the candidates are (Number) (Shape)");
+}
+
+// the Circle isn't derived from the new Shape, the method chosen before for it is forgotten
+#[test]
+fn rebind_proto_clears_table() {
+    let code = String::from(SHAPE) + "(main) = {\n(show Circle)\nShape <- @Shape{}\n(show Circle)\n}";
+    assert_eq!(run(&code).unwrap_err(), "no method of 'area' is applicable to (Object), the candidates are (Number) (Shape)");
+}
+
+// the widths aren't kept at runtime, so the methods differ only in them can't be chosen by values
+#[test]
+fn methods_differ_only_in_widths() {
+    let errors = errors_of("for apply (w ...) {\n    (a:i32) = {32}\n    (a:i64) = {64}\n}\n(main) = (w 1i64)");
+    assert_eq!(errors, vec!["methods of 'w' are ambiguous at runtime, (i32) and (i64) differ only in the widths of number"]);
+    let errors = errors_of("for apply (w ...) {\n    (a:f64 b:String) = {1}\n    (a:Float b:String) = {2}\n}");
+    assert_eq!(errors, vec!["methods of 'w' are ambiguous at runtime, (f64 String) and (Float String) differ only in the widths of number"]);
+    let code = "for apply (w ...) {\n    (a:i32) = {\"int\"}\n    (a:f32) = {\"float\"}\n}\n(main) = [(w 1i32) (w 2.5f32)]";
+    assert_eq!(run(code).unwrap(), "[int float]");
+}
@@ -0,0 +1,54 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+const SHAPES:(&str, &str) = ("geo/shapes.fa", "module geo.shapes\npi = 3\npub (area r) = (* pi r r)\npub (square a) = (* a a)\n");
+
+#[test]
+fn import_by_alias_and_names() {
+    let main = "import geo.shapes as s {area}\n(main) = [(area 2) (s.square 3)]";
+    assert_eq!(run_files("alias", &[("main.fa", main), SHAPES]).unwrap(), "[12 9]");
+}
+
+#[test]
+fn private_and_missing_names() {
+    let main = "import geo.shapes {pi nothing}\n(main) = 1";
+    assert_eq!(run_files("private", &[("main.fa", main), SHAPES]).unwrap_err(), vec![
+        "'pi' is private in module 'geo.shapes'",
+        "module 'geo.shapes' has no 'nothing'",
+    ]);
+}
+
+#[test]
+fn import_cycle() {
+    let files = [("main.fa", "import a\n(main) = a.x"), ("a.fa", "import b\npub x = 1"), ("b.fa", "import a\npub y = 2")];
+    assert_eq!(run_files("cycle", &files).unwrap_err(), vec!["import cycle: a -> b -> a"]);
+}
+
+#[test]
+fn missing_module() {
+    let errors = run_files("missing", &[("main.fa", "import nope\n(main) = 1")]).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("can't find module 'nope' in: "), "{:?}", errors);
+}
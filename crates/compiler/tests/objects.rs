@@ -0,0 +1,52 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+#[test]
+fn object_literal() {
+    assert_eq!(run("(main) = @{a: 1 b: \"s\"}").unwrap(), "@{a: 1 b: s}");
+    assert_eq!(run("(main) = {\n    o = @{a: @{b: 2}}\n    o.a.b\n}").unwrap(), "2");
+}
+
+#[test]
+fn get_and_set_slot() {
+    assert_eq!(run("(main) = {\n    o = @{a: 1 b: 2}\n    [o.a o.b]\n}").unwrap(), "[1 2]");
+    assert_eq!(run("(main) = {\n    o = @{a: 1}\n    o.a <- 5\n    o.a\n}").unwrap(), "5");
+}
+
+// the slot not in object is looked up in the parent, but the set is always to the object
+#[test]
+fn parent_chain() {
+    assert_eq!(run("(main) = {\n    p = @{a: 1 b: 2}\n    o = @p{b: 3}\n    [o.a o.b p.b]\n}").unwrap(), "[1 3 2]");
+    assert_eq!(run("(main) = {\n    p = @{a: 1}\n    o = @p{}\n    o.a <- 7\n    [o.a p.a]\n}").unwrap(), "[7 1]");
+    assert_eq!(run("(main) = {\n    g = @{a: 1}\n    p = @g{}\n    o = @p{}\n    g.a <- 9\n    o.a\n}").unwrap(), "9");
+}
+
+#[test]
+fn missing_slot() {
+    let code = "(main) = {\n    o = @{a: 1}\n    o.z\n}";
+    assert_eq!(errors_of(code), vec!["type {a: Number} has no slot 'z'"]);
+    assert_eq!(execute(&build(code).0).unwrap_err(), "no slot 'z' in @{a: 1}");
+    assert_eq!(errors_of("(main) = {\n    x = 5\n    x.a\n}"), vec!["type Number has no slot 'a'"]);
+}
@@ -0,0 +1,61 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+fn resolve_errors(code:&str) -> Vec<String> {
+    describes(resolve(code).errors())
+}
+
+#[test]
+fn undefined_name_with_suggestion() {
+    assert_eq!(resolve_errors("(f count) = (+ cuont 1)"), vec!["name 'cuont' is not defined, did you mean 'count'?"]);
+}
+
+// the name isn't visible here, but it's in the Pool
+#[test]
+fn suggestion_from_other_scope() {
+    let code = "(g) = {\n    counter = 3\n    counter\n}\n(f) = countr";
+    assert_eq!(resolve_errors(code), vec!["name 'countr' is not defined, did you mean 'counter'?"]);
+}
+
+#[test]
+fn duplicate_definitions() {
+    assert_eq!(resolve_errors("x = 1\nx = 2"), vec!["global 'x' is defined again"]);
+    assert_eq!(resolve_errors("(f a a) = a"), vec!["parameter 'a' is defined again"]);
+    assert_eq!(resolve_errors("(f) = {\n    y = 1\n    y = 2\n    y\n}"), vec!["local 'y' is defined again"]);
+}
+
+#[test]
+fn shadowing_is_warning() {
+    let the_diag = resolve("x = 1\n(f) = {\n    x = 2\n    x\n}");
+    assert!(!the_diag.has_error());
+    let warnings:Vec<String> = the_diag.warnings().iter().map(|a| a.describe()).collect();
+    assert_eq!(warnings, vec!["'x' shadows the global defined before"]);
+}
+
+// the define in block is reported by parser, the uses of it aren't reported again
+#[test]
+fn define_in_block_is_one_error() {
+    assert_eq!(errors_of("(f x) = {\n    (g y) = (+ y 1)\n    (g x)\n}\n(main) = (f 1)"), vec!["'g' should be defined at top level"]);
+}
@@ -0,0 +1,83 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+fn check_errors(code:&str) -> Vec<String> {
+    describes(check(code).errors())
+}
+
+#[test]
+fn mismatched_argument() {
+    assert_eq!(check_errors("(f x:String) = x\n(main) = (f 1)"), vec!["mismatched types: expected String but found Number"]);
+    assert_eq!(check_errors("(f x:i32) = x\n(main) = (f 1i64)"), vec!["mismatched types: expected i32 but found i64"]);
+}
+
+// the number literal without suffix takes the width expected
+#[test]
+fn literal_takes_fixed_width() {
+    assert!(check_errors("(f x:i32) = x\n(main) = (f 1)").is_empty());
+    assert!(check_errors("(f x:u8) = x\n(main) = (f 255)").is_empty());
+    assert!(check_errors("(f x:f32) = x\n(main) = (f 1.5)").is_empty());
+    assert!(check_errors("x:i64 = -5\n(main) = x").is_empty());
+    assert_eq!(run("(f x:i32) = (+ x 1)\n(main) = (f 2)").unwrap(), "3");
+}
+
+#[test]
+fn literal_out_of_range() {
+    assert_eq!(check_errors("(f x:u8) = x\n(main) = (f 300)"), vec!["number '300' out of range for 'u8' (0..=255)"]);
+    assert_eq!(check_errors("(f x:u8) = x\n(main) = (f -1)"), vec!["number '-1' out of range for 'u8' (0..=255)"]);
+    assert_eq!(check_errors("x:i8 = 128"), vec!["number '128' out of range for 'i8' (-128..=127)"]);
+}
+
+// only the literal takes it, the integer isn't a float and a Number local isn't fixed
+#[test]
+fn width_isnt_taken_by_others() {
+    assert_eq!(check_errors("(f x:f32) = x\n(main) = (f 1)"), vec!["mismatched types: expected f32 but found Number"]);
+    assert_eq!(check_errors("(f x:i32) = x\n(main) = {\n    y = 1\n    (f y)\n}"), vec!["mismatched types: expected i32 but found Number"]);
+}
+
+// the item of [] is Any if nothing use it, but it's the type expected if there is
+#[test]
+fn empty_list() {
+    assert!(check_errors("(main) = (println! [])").is_empty());
+    assert_eq!(run("(main) = {\n    x = []\n    x\n}").unwrap(), "[]");
+    assert!(check_errors("(f xs:List<String>) = xs\n(main) = (f [])").is_empty());
+}
+
+// the 'auto' is solved by the uses, or it's an error
+#[test]
+fn auto() {
+    assert!(check_errors("(main) = {\n    x:auto = 1\n    (+ x 1)\n}").is_empty());
+    assert!(check_errors("(main) = {\n    x:auto = []\n    x\n}").is_empty());
+    assert_eq!(check_errors("(f a:auto) = a"), vec!["can't infer the type of parameter 'a', it needs a type annotation"]);
+    assert_eq!(check_errors("(f a:auto) = (+ a 1)\n(main) = (f \"s\")"), vec!["mismatched types: expected Number but found String"]);
+}
+
+// the unannotated parameter is inferred by the body, or it's Any
+#[test]
+fn parameter_inference() {
+    assert_eq!(check_errors("(f a) = (+ a 1)\n(main) = (f \"s\")"), vec!["mismatched types: expected Number but found String"]);
+    assert_eq!(run("(f a) = (+ a 1)\n(main) = (f 2)").unwrap(), "3");
+    assert_eq!(run("(f a b) = [a b]\n(main) = (f 1 \"s\")").unwrap(), "[1 s]");
+}
@@ -19,3 +19,154 @@
 // LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
+
+use std::rc::Rc;
+use farvm_vm::{Builtin, Host, Value};
+
+// the builtins of std, they're found by name when run
+pub fn builtins() -> Vec<Builtin> {
+    vec![
+        Builtin { name: "print!", call: print },
+        Builtin { name: "println!", call: println },
+        Builtin { name: "+", call: add },
+        Builtin { name: "-", call: sub },
+        Builtin { name: "*", call: mul },
+        Builtin { name: "/", call: div },
+        Builtin { name: "==", call: equal },
+        Builtin { name: "<", call: less },
+        Builtin { name: ">", call: greater },
+        Builtin { name: "<=", call: less_equal },
+        Builtin { name: ">=", call: greater_equal },
+        Builtin { name: "not", call: not },
+        Builtin { name: "and", call: and },
+        Builtin { name: "or", call: or },
+        Builtin { name: "each", call: each },
+        Builtin { name: "pipe", call: pipe },
+    ]
+}
+
+pub fn install(cpu:&mut farvm_vm::Object) {
+    for a in builtins() {
+        cpu.install(a);
+    }
+}
+
+fn text_of(args:&[Value]) -> String {
+    args.iter().map(|a| a.to_string()).collect::<Vec<String>>().join(" ")
+}
+fn print(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    print!("{}", text_of(args));
+    Ok(Value::Nil)
+}
+fn println(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    println!("{}", text_of(args));
+    Ok(Value::Nil)
+}
+
+// the Integer is checked for overflow, it's Float if any is Float
+fn arithmetic(args:&[Value], integer:fn(i64, i64) -> Option<i64>, float:fn(f64, f64) -> f64) -> Result<Value, String> {
+    let Some((first, rest)) = args.split_first() else {
+        return Err(String::from("expects 1 argument(s) at least"))
+    };
+    rest.iter().try_fold(first.clone(), |a, b| match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            integer(a, *b).map(Value::Integer).ok_or_else(|| format!("overflow of {} and {}", a, b))
+        }
+        (Value::Integer(a), Value::Float(b)) => {Ok(Value::Float(float(a as f64, *b)))}
+        (Value::Float(a), Value::Integer(b)) => {Ok(Value::Float(float(a, *b as f64)))}
+        (Value::Float(a), Value::Float(b)) => {Ok(Value::Float(float(a, *b)))}
+        (a, b) => {Err(format!("expects Number or Float but found {} and {}", a.type_name(), b.type_name()))}
+    })
+}
+fn add(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    match args {
+        [Value::String(_), ..] => {Ok(Value::String(args.iter().map(|a| a.to_string()).collect()))}
+        _ => {arithmetic(args, i64::checked_add, |a, b| a + b)}
+    }
+}
+fn sub(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    match args {
+        [Value::Integer(a)] => {a.checked_neg().map(Value::Integer).ok_or_else(|| format!("overflow of -{}", a))}
+        [Value::Float(a)] => {Ok(Value::Float(-a))}
+        _ => {arithmetic(args, i64::checked_sub, |a, b| a - b)}
+    }
+}
+fn mul(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    arithmetic(args, i64::checked_mul, |a, b| a * b)
+}
+fn div(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    if let [Value::Integer(_), rest @ ..] = args {
+        if rest.contains(&Value::Integer(0)) {
+            return Err(String::from("divide by zero"))
+        }
+    }
+    arithmetic(args, i64::checked_div, |a, b| a / b)
+}
+
+fn compare(args:&[Value], ok:fn(std::cmp::Ordering) -> bool) -> Result<Value, String> {
+    for pair in args.windows(2) {
+        let ordering = match (&pair[0], &pair[1]) {
+            (Value::Integer(a), Value::Integer(b)) => {Some(a.cmp(b))}
+            (Value::Integer(a), Value::Float(b)) => {(*a as f64).partial_cmp(b)}
+            (Value::Float(a), Value::Integer(b)) => {a.partial_cmp(&(*b as f64))}
+            (Value::Float(a), Value::Float(b)) => {a.partial_cmp(b)}
+            (Value::Char(a), Value::Char(b)) => {Some(a.cmp(b))}
+            (Value::String(a), Value::String(b)) => {Some(a.cmp(b))}
+            (a, b) => {return Err(format!("can't compare {} and {}", a.type_name(), b.type_name()))}
+        };
+        if !matches!(ordering, Some(a) if ok(a)) {
+            return Ok(Value::Bool(false))
+        }
+    }
+    Ok(Value::Bool(true))
+}
+fn equal(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(args.windows(2).all(|a| a[0] == a[1])))
+}
+fn less(_:&mut Host, args:&[Value]) -> Result<Value, String> {compare(args, |a| a.is_lt())}
+fn greater(_:&mut Host, args:&[Value]) -> Result<Value, String> {compare(args, |a| a.is_gt())}
+fn less_equal(_:&mut Host, args:&[Value]) -> Result<Value, String> {compare(args, |a| a.is_le())}
+fn greater_equal(_:&mut Host, args:&[Value]) -> Result<Value, String> {compare(args, |a| a.is_ge())}
+
+fn truth_of(a:&Value) -> Result<bool, String> {
+    match a {
+        Value::Bool(a) => {Ok(*a)}
+        _ => {Err(format!("expects Bool but found {}", a.type_name()))}
+    }
+}
+fn not(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    match args {
+        [a] => {Ok(Value::Bool(!truth_of(a)?))}
+        _ => {Err(format!("expects 1 argument(s) but found {}", args.len()))}
+    }
+}
+fn and(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    args.iter().try_fold(true, |a, b| Ok(truth_of(b)? && a)).map(Value::Bool)
+}
+fn or(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    args.iter().try_fold(false, |a, b| Ok(truth_of(b)? || a)).map(Value::Bool)
+}
+
+// (each f list), the f is called with every item in order, the results are dropped
+fn each(host:&mut Host, args:&[Value]) -> Result<Value, String> {
+    match args {
+        [f, Value::List(items)] => {
+            for a in items.iter() {
+                host.call(f, vec![a.clone()])?;
+            }
+            Ok(Value::Nil)
+        }
+        [_, a] => {Err(format!("expects List but found {}", a.type_name()))}
+        _ => {Err(format!("expects 2 argument(s) but found {}", args.len()))}
+    }
+}
+// (pipe f g ...), the function calls f then g with the result of f
+fn pipe(_:&mut Host, args:&[Value]) -> Result<Value, String> {
+    if args.is_empty() {
+        return Err(String::from("expects 1 argument(s) at least"))
+    }
+    if let Some(a) = args.iter().find(|a| a.type_name() != "Function") {
+        return Err(format!("expects Function but found {}", a.type_name()))
+    }
+    Ok(Value::Pipe(Rc::new(args.to_vec())))
+}
@@ -0,0 +1,220 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{Literal, Node};
+use farvm_utils::{Pool, Source, Span};
+
+// The tree in JSON, it's stable for the tools out of Rust, the keys of object are always in the order below
+//   Root = {"format": "farvm-tree", "version": 1, "files": [{"id": FileId, "name": String}], "tree": Node}
+//   Span = {"file": FileId, "start": Byte, "end": Byte, "line": Row, "column": Col}
+//     the start and end are byte offsets of the code, the end is exclusive
+//     the line and column are of the start and 1-based, the column is counted by the column unit of Source
+//   Node = {"kind": Kind, "span": Span, ...the fields of kind}, the names are the strings from Pool
+//     Module          items:[Node]
+//     Define          name:String generics:[Node] params:[Node] body:Node
+//     Param           name:String type:(Node or null)
+//     TypeParam       name:String
+//     TypeRef         name:String
+//     AutoType
+//     Generic         base:Node args:[Node]
+//     FunctionType    params:[Node] return:Node
+//     DispatchGroup   strategy:String name:String variadic:Bool methods:[Node]
+//     Call            callee:Node args:[Node]
+//     MacroCall       name:String args:[Node], the name is with '!'
+//     MacroDefine     name:String params:[Node] variadic:Bool body:Node
+//     Block           items:[Node]
+//     List            items:[Node]
+//     Literal         type:("integer" "float" "string" "char" "bool" "nil") value:(String Bool null) suffix:String
+//                       the value of integer and float is the canonical decimal text, the suffix is like "i32" or ""
+//     Format          parts:[Node]
+//     Ident           name:String
+//     MemberAccess    object:Node member:String
+//     ObjectLiteral   parent:(Node or null) slots:[Node]
+//     Slot            name:String value:Node
+//     Assign          name:String type:(Node or null) value:Node
+//     Rebind          target:Node value:Node
+//     ModuleDecl      path:String
+//     Import          path:String alias:(String or null) names:[Node]
+//     Public          item:Node
+//     Error
+//     Empty
+pub fn pass(tree:&Node, pool:&Pool, source:&Source) -> String {
+    let files = (0..source.file_count() as u32).map(|a| Json::Object(vec![
+        ("id", Json::Number(a as u64)),
+        ("name", Json::String(String::from(source.name(a)))),
+    ])).collect();
+    let root = Json::Object(vec![
+        ("format", Json::String(String::from("farvm-tree"))),
+        ("version", Json::Number(1)),
+        ("files", Json::Array(files)),
+        ("tree", node(tree, pool, source)),
+    ]);
+    let mut text = String::new();
+    root.write(&mut text, 0);
+    text
+}
+
+enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    // with 2 spaces indent, the empty array and object are in one line
+    fn write(&self, text:&mut String, depth:usize) {
+        let indent = "  ".repeat(depth + 1);
+        match self {
+            Json::Null => {text.push_str("null")}
+            Json::Bool(a) => {text.push_str(if *a {"true"} else {"false"})}
+            Json::Number(a) => {text.push_str(&a.to_string())}
+            Json::String(a) => {write_string(text, a)}
+            Json::Array(items) if items.is_empty() => {text.push_str("[]")}
+            Json::Array(items) => {
+                text.push('[');
+                for (i, a) in items.iter().enumerate() {
+                    text.push_str(if i == 0 {"\n"} else {",\n"});
+                    text.push_str(&indent);
+                    a.write(text, depth + 1);
+                }
+                text.push('\n');
+                text.push_str(&"  ".repeat(depth));
+                text.push(']');
+            }
+            Json::Object(fields) if fields.is_empty() => {text.push_str("{}")}
+            Json::Object(fields) => {
+                text.push('{');
+                for (i, (key, a)) in fields.iter().enumerate() {
+                    text.push_str(if i == 0 {"\n"} else {",\n"});
+                    text.push_str(&indent);
+                    write_string(text, key);
+                    text.push_str(": ");
+                    a.write(text, depth + 1);
+                }
+                text.push('\n');
+                text.push_str(&"  ".repeat(depth));
+                text.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(text:&mut String, a:&str) {
+    text.push('"');
+    for c in a.chars() {
+        match c {
+            '"' => {text.push_str("\\\"")}
+            '\\' => {text.push_str("\\\\")}
+            '\n' => {text.push_str("\\n")}
+            '\r' => {text.push_str("\\r")}
+            '\t' => {text.push_str("\\t")}
+            c if (c as u32) < 0x20 => {text.push_str(&format!("\\u{:04x}", c as u32))}
+            c => {text.push(c)}
+        }
+    }
+    text.push('"');
+}
+
+fn span_of(span:Span, source:&Source) -> Json {
+    let (line, column) = source.start_of(span);
+    Json::Object(vec![
+        ("file", Json::Number(span.file as u64)),
+        ("start", Json::Number(span.start as u64)),
+        ("end", Json::Number(span.end as u64)),
+        ("line", Json::Number(line as u64)),
+        ("column", Json::Number(column as u64)),
+    ])
+}
+
+fn node(a:&Node, pool:&Pool, source:&Source) -> Json {
+    let name = |a:u32| Json::String(pool.str_at(a));
+    let one = |a:&Node| node(a, pool, source);
+    let many = |nodes:&[Node]| Json::Array(nodes.iter().map(|a| node(a, pool, source)).collect());
+    let (kind, fields):(&'static str, Vec<(&'static str, Json)>) = match a {
+        Node::Module{items, ..} => {("Module", vec![("items", many(items))])}
+        Node::Define{name: n, generics, params, body, ..} => {
+            ("Define", vec![("name", name(*n)), ("generics", many(generics)), ("params", many(params)), ("body", one(body))])
+        }
+        Node::Param{name: n, ty, ..} => {
+            ("Param", vec![("name", name(*n)), ("type", ty.as_ref().map_or(Json::Null, |a| one(a)))])
+        }
+        Node::TypeParam{name: n, ..} => {("TypeParam", vec![("name", name(*n))])}
+        Node::TypeRef{name: n, ..} => {("TypeRef", vec![("name", name(*n))])}
+        Node::AutoType{..} => {("AutoType", Vec::new())}
+        Node::Generic{base, args, ..} => {("Generic", vec![("base", one(base)), ("args", many(args))])}
+        Node::FunctionType{params, ret, ..} => {("FunctionType", vec![("params", many(params)), ("return", one(ret))])}
+        Node::DispatchGroup{strategy, name: n, variadic, methods, ..} => {
+            ("DispatchGroup", vec![
+                ("strategy", name(*strategy)), ("name", name(*n)), ("variadic", Json::Bool(*variadic)), ("methods", many(methods)),
+            ])
+        }
+        Node::Call{callee, args, ..} => {("Call", vec![("callee", one(callee)), ("args", many(args))])}
+        Node::MacroCall{name: n, args, ..} => {
+            ("MacroCall", vec![("name", Json::String(pool.str_at(*n) + "!")), ("args", many(args))])
+        }
+        Node::MacroDefine{name: n, params, variadic, body, ..} => {
+            ("MacroDefine", vec![
+                ("name", Json::String(pool.str_at(*n) + "!")), ("params", many(params)),
+                ("variadic", Json::Bool(*variadic)), ("body", one(body)),
+            ])
+        }
+        Node::Block{items, ..} => {("Block", vec![("items", many(items))])}
+        Node::List{items, ..} => {("List", vec![("items", many(items))])}
+        Node::Literal{value, ..} => {("Literal", literal(value, pool))}
+        Node::Format{parts, ..} => {("Format", vec![("parts", many(parts))])}
+        Node::Ident{name: n, ..} => {("Ident", vec![("name", name(*n))])}
+        Node::MemberAccess{object, member, ..} => {("MemberAccess", vec![("object", one(object)), ("member", name(*member))])}
+        Node::ObjectLiteral{parent, slots, ..} => {
+            ("ObjectLiteral", vec![("parent", parent.as_ref().map_or(Json::Null, |a| one(a))), ("slots", many(slots))])
+        }
+        Node::Slot{name: n, value, ..} => {("Slot", vec![("name", name(*n)), ("value", one(value))])}
+        Node::Assign{name: n, ty, value, ..} => {
+            ("Assign", vec![("name", name(*n)), ("type", ty.as_ref().map_or(Json::Null, |a| one(a))), ("value", one(value))])
+        }
+        Node::Rebind{target, value, ..} => {("Rebind", vec![("target", one(target)), ("value", one(value))])}
+        Node::ModuleDecl{path, ..} => {("ModuleDecl", vec![("path", name(*path))])}
+        Node::Import{path, alias, names, ..} => {
+            ("Import", vec![("path", name(*path)), ("alias", alias.map_or(Json::Null, name)), ("names", many(names))])
+        }
+        Node::Public{item, ..} => {("Public", vec![("item", one(item))])}
+        Node::Error{..} => {("Error", Vec::new())}
+        Node::_Empty => {("Empty", Vec::new())}
+    };
+    let mut object = vec![("kind", Json::String(String::from(kind))), ("span", span_of(a.span(), source))];
+    object.extend(fields);
+    Json::Object(object)
+}
+
+fn literal(value:&Literal, pool:&Pool) -> Vec<(&'static str, Json)> {
+    let (ty, value, suffix) = match value {
+        Literal::Integer(a, suffix) => {("integer", Json::String(pool.str_at(*a)), suffix.name())}
+        Literal::Float(a, suffix) => {("float", Json::String(pool.str_at(*a)), suffix.name())}
+        Literal::String(a) => {("string", Json::String(a.clone()), "")}
+        Literal::Char(a) => {("char", Json::String(a.to_string()), "")}
+        Literal::Bool(a) => {("bool", Json::Bool(*a), "")}
+        Literal::Nil => {("nil", Json::Null, "")}
+    };
+    vec![("type", Json::String(String::from(ty))), ("value", value), ("suffix", Json::String(String::from(suffix)))]
+}
@@ -20,31 +20,80 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::collections::btree_map::Values;
-
-use super::{TokenLoc,TokenValue,Token,Lexer};
-use farvm_utils::{Diag, Pool, pool};
+use super::{TokenValue,Token,Trivia,TriviaKind,FormatPiece,NumberSuffix,Lexer};
+use super::unicode_id;
+use farvm_utils::{Diag, Pool, pool, Span, source::FileId};
 
+// All positions are byte offset of code, the [at, at_end) is the char in jump
 pub struct Object {
     code: Vec<u8>,
+    file: FileId,
     i: usize,
-    row: u32,
-    col: u32,
-    take_loc: TokenLoc,
+    at: usize,
+    at_end: usize,
+    take_start: usize,
+    take_end: usize,
     take_raw: String,
-    back_char: char,
+    take_pieces: Vec<FormatPiece>,
+    take_quote: char,
+    take_close: String,
+    close_seen: usize,
+    escape_raw: String,
+    brace_at: usize,
+    back_chars: Vec<(char, usize, usize)>,
+    bad_utf8: Vec<(usize, usize)>,
+    lossless: bool,
+    last_end: usize,
+}
+
+pub fn make(code:&[u8], file:FileId) -> Object {
+    make_with(code, file, false)
+}
+
+// the lossless lexer keep the comments and whitespaces as trivia of tokens
+pub fn make_lossless(code:&[u8], file:FileId) -> Object {
+    make_with(code, file, true)
 }
 
-pub fn make(code:&str) -> Object {
+fn make_with(code:&[u8], file:FileId, lossless:bool) -> Object {
     Object {
-        code: Vec::from(code.as_bytes()),
+        code: Vec::from(code),
+        file,
         i: 0,
-        row: 1,
-        col: 1,
-        take_loc: TokenLoc::default(),
+        at: 0,
+        at_end: 0,
+        take_start: 0,
+        take_end: 0,
         take_raw: String::new(),
-        back_char: '\0',
+        take_pieces: Vec::new(),
+        take_quote: '"',
+        take_close: String::new(),
+        close_seen: 0,
+        escape_raw: String::new(),
+        brace_at: 0,
+        back_chars: Vec::new(),
+        bad_utf8: find_bad_utf8(code),
+        lossless,
+        last_end: 0,
+    }
+}
+
+// The (start, length) of every invalid UTF-8 sequence in code, order by start
+fn find_bad_utf8(code:&[u8]) -> Vec<(usize, usize)> {
+    let mut bads = Vec::new();
+    let mut i = 0;
+    while i < code.len() {
+        match std::str::from_utf8(&code[i..]) {
+            Ok(_) => {break}
+            Err(e) => {
+                let start = i + e.valid_up_to();
+                let len = e.error_len().unwrap_or(code.len() - start);
+                bads.push((start, len));
+                i = start + len;
+            }
+        }
     }
+    bads
 }
 
 enum JumpTo {
@@ -52,26 +101,88 @@ enum JumpTo {
     Symbol2,
     Comment,
     Id,
+    Number,
     String,
+    StringOpen,
+    StringQuote2,
+    RawStringOpen,
+    Verbatim,
     StringSlash,
+    StringUnicode,
+    Char,
+    StringLeftBrace,
+    StringRightBrace,
     Take(TokenValue),
 }
 
 impl Object {
-    fn now_are_token_loc(&mut self) -> &mut Self {
-        self.take_loc = TokenLoc{ row: self.row, col: self.col };
+    fn now_are_token_start(&mut self) -> &mut Self {
+        self.take_start = self.at;
+        self.take_end = self.at_end;
         self
     }
+    fn now_are_token_end(&mut self) -> &mut Self {
+        self.take_end = self.at_end;
+        self
+    }
+    fn take_span(&self) -> Span {
+        Span::new(self.file, self.take_start, self.take_end)
+    }
+    fn take_token(&mut self, val:TokenValue) -> Token {
+        let span = self.take_span();
+        let trivia = if self.lossless {self.trivia_between(self.last_end, self.take_start)} else {Vec::new()};
+        self.last_end = self.take_end;
+        let line_start = self.take_start == 0 || self.code[self.take_start - 1] == b'\n';
+        Token { span, val, trivia, line_start }
+    }
+    // the code between tokens is only whitespaces, comments and the bad chars skipped by lexer
+    //   Trivia = (any (or Whitespace DocComment Comment Skipped))
+    //   DocComment = (order "##" (any-not "\n"))
+    fn trivia_between(&self, start:usize, end:usize) -> Vec<Trivia> {
+        let mut trivia:Vec<Trivia> = Vec::new();
+        let mut i = start;
+        while i < end {
+            let (a, len) = decode_utf8(&self.code[i..end]);
+            let (kind, len) = if a == '#' {
+                let len = self.code[i..end].iter().position(|&b| b == b'\n').unwrap_or(end - i);
+                let is_doc = self.code[i..end].starts_with(b"##");
+                (if is_doc {TriviaKind::DocComment} else {TriviaKind::Comment}, len)
+            } else if a.is_whitespace() {
+                (TriviaKind::Whitespace, len)
+            } else {
+                (TriviaKind::Skipped, len)
+            };
+            match trivia.last_mut() {
+                Some(last) if last.kind == kind && kind != TriviaKind::Comment && kind != TriviaKind::DocComment => {
+                    last.span.end = (i + len) as u32;
+                }
+                _ => {trivia.push(Trivia { kind, span: Span::new(self.file, i, i + len) })}
+            }
+            i += len;
+        }
+        trivia
+    }
     fn reset_take_raw(&mut self) -> &mut Self {
         self.take_raw.clear();
         self
     }
     fn take_raw_push(&mut self, a:char) -> &mut Self {
         self.take_raw.push(a);
-        self
+        self.now_are_token_end()
     }
     fn take_string(&mut self) -> TokenValue {
-        TokenValue::LiteralString(self.take_raw.clone())
+        if self.take_pieces.is_empty() {
+            TokenValue::LiteralString(self.take_raw.clone())
+        } else {
+            self.take_text_piece();
+            TokenValue::LiteralFormat(std::mem::take(&mut self.take_pieces))
+        }
+    }
+    fn take_text_piece(&mut self) {
+        if !self.take_raw.is_empty() {
+            let text = std::mem::take(&mut self.take_raw);
+            self.take_pieces.push(FormatPiece::Text(text));
+        }
     }
     fn take_pool_id(&self, pool:&mut Pool) -> pool::Id {
         pool.add_str(self.take_raw.clone())
@@ -84,24 +195,40 @@ impl Object {
             "nil" => {TokenValue::KeyNil}
             "true" => {TokenValue::KeyTrue}
             "false" => {TokenValue::KeyFalse}
+            "module" => {TokenValue::KeyModule}
+            "import" => {TokenValue::KeyImport}
+            "pub" => {TokenValue::KeyPub}
+            "as" => {TokenValue::KeyAs}
+            raw if raw.len() > 1 && raw.ends_with('!') => {
+                TokenValue::MacroId(pool.add_str(&raw[..raw.len()-1]))
+            }
             _ => {TokenValue::Id(self.take_pool_id(pool))}
         }
     }
+    // the '\0' is end of code, it's never to back
     fn back_one(&mut self, a:char) -> &mut Self {
-        assert!(self.take_raw.len() > 0);
-        assert!(self.back_char == '\0');
-        assert!(a != '\0');
-        self.back_char = a;
+        if a != '\0' {
+            self.back_chars.push((a, self.at, self.at_end));
+        }
         self
     }
-    fn pop_back(&mut self) -> char {
-        assert!(self.back_char != '\0');
-        let a = self.back_char;
-        self.back_char = '\0';
-        a
+    fn pop_back(&mut self) -> Option<char> {
+        let (a, at, at_end) = self.back_chars.pop()?;
+        self.at = at;
+        self.at_end = at_end;
+        Some(a)
+    }
+    fn error_at(&self, diag:&mut Diag, start:usize, end:usize, describe:&str) {
+        diag.push_error(Span::new(self.file, start, end), describe);
+    }
+    fn error_at_now(&self, diag:&mut Diag, describe:&str) {
+        self.error_at(diag, self.at, self.at_end, describe)
     }
-    fn has_back(&self) -> bool {
-        self.back_char != '\0'
+    // the replacement-char is from invalid UTF-8, it's reported by get_char_by_pass
+    fn error_bad_id_char(&self, a:char, diag:&mut Diag) {
+        if a != char::REPLACEMENT_CHARACTER {
+            self.error_at_now(diag, &format!("character '{}' (U+{:04X}) can't be in identifier", a, a as u32));
+        }
     }
 
     // Comment = (order (one '#') (any-not (one-in "\n\0")))
@@ -112,104 +239,433 @@ impl Object {
         }
     }
 
-    // Id = (one-or-many (and (one-not-in "()[]{}<>:.,") (> ASCII 32)))
-    fn on_id(&mut self, a:char, pool:&mut Pool) -> JumpTo {
+    // RawStringOpen = (order (one 'r') (any '#') (one '"'))
+    //   or it's an Id like "r#x" if the '"' isn't after '#'
+    fn on_raw_string_open(&mut self, a:char, pool:&mut Pool, diag:&mut Diag) -> JumpTo {
+        match a {
+            '#' => {self.take_raw_push(a); JumpTo::RawStringOpen}
+            '"' => {
+                self.take_close = format!("\"{}", &self.take_raw[1..]);
+                self.reset_take_raw();
+                JumpTo::Verbatim
+            }
+            _ => {self.on_id(a, pool, diag)}
+        }
+    }
+
+    // StringOpen = (order (one '"') (or StringQuote2 String))
+    fn on_string_open(&mut self, a:char, diag:&mut Diag) -> JumpTo {
+        match a {
+            '"' => {JumpTo::StringQuote2}
+            _ => {self.on_string(a, diag)}
+        }
+    }
+    // StringQuote2 = (order "\"\"" (or (one '"') Empty)), the '"' is start of TripleString
+    fn on_string_quote2(&mut self, a:char) -> JumpTo {
+        match a {
+            '"' => {
+                self.take_close = String::from("\"\"\"");
+                JumpTo::Verbatim
+            }
+            _ => {
+                self.take_end = self.at;
+                self.back_one(a);
+                JumpTo::Take(TokenValue::LiteralString(String::new()))
+            }
+        }
+    }
+
+    // Verbatim = (order (any-not take_close) take_close), there are no escape and "{expr}" in it
+    //   RawString = (order (one 'r') (any '#') (one '"') Verbatim), take_close is (order '"' (same-count '#'))
+    //   TripleString = (order "\"\"\"" Verbatim), take_close is "\"\"\"" and the indent of lines will strip
+    fn on_verbatim(&mut self, a:char, diag:&mut Diag) -> JumpTo {
+        if a == '\0' {
+            self.error_at(diag, self.take_start, self.take_start + 1, &format!("unterminated string, lack of '{}'", self.take_close));
+            let seen = String::from(&self.take_close[..self.close_seen]);
+            self.take_raw.push_str(&seen);
+            self.close_seen = 0;
+            self.now_are_token_end();
+            return JumpTo::Take(self.take_verbatim())
+        }
+        if self.take_close[self.close_seen..].starts_with(a) {
+            self.close_seen += 1;
+            if self.close_seen == self.take_close.len() {
+                self.close_seen = 0;
+                self.now_are_token_end();
+                return JumpTo::Take(self.take_verbatim())
+            }
+            return JumpTo::Verbatim
+        }
+        if self.close_seen > 0 {
+            // the seen is text but not close, a may be start of close again
+            let seen = String::from(&self.take_close[..self.close_seen]);
+            self.take_raw.push_str(&seen);
+            self.close_seen = 0;
+            return self.on_verbatim(a, diag)
+        }
+        self.take_raw_push(a);
+        JumpTo::Verbatim
+    }
+    fn take_verbatim(&mut self) -> TokenValue {
+        let text = std::mem::take(&mut self.take_raw);
+        if self.take_close == "\"\"\"" {
+            TokenValue::LiteralString(strip_indent(&text))
+        } else {
+            TokenValue::LiteralString(text)
+        }
+    }
+
+    // Id = (order IdStart (any IdContinue))
+    //   IdStart = (or (and ASCII (one-not-in "()[]{}<>:.,") (> ASCII 32)) XID_Start)
+    //   IdContinue = (or (and ASCII (one-not-in "()[]{}<>:.,\"") (> ASCII 32)) XID_Continue)
+    //   the "->" is ARROW of function type, not Id
+    fn on_id(&mut self, a:char, pool:&mut Pool, diag:&mut Diag) -> JumpTo {
         match a {
-            '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>' | ':' | '.' | ',' => {self.back_one(a);}
+            '0'..='9' if self.take_raw == "-" => {
+                self.take_raw_push(a);
+                return JumpTo::Number
+            }
+            '"' | '#' if self.take_raw == "r" => {
+                return self.on_raw_string_open(a, pool, diag)
+            }
+            '>' if self.take_raw == "-" => {
+                self.now_are_token_end();
+                return JumpTo::Take(TokenValue::ARROW)
+            }
+            '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>' | ':' | '.' | ',' | '"' => {self.back_one(a);}
             _ => {
-                if a > ' ' {
+                if a.is_ascii() {
+                    if a > ' ' {
+                        self.take_raw_push(a);
+                        return JumpTo::Id
+                    }
+                } else if is_unicode_id_continue(a) {
                     self.take_raw_push(a);
                     return JumpTo::Id
+                } else if !a.is_whitespace() {
+                    self.error_bad_id_char(a, diag);
+                    return JumpTo::Id
                 }
             }
         }
         JumpTo::Take(self.match_id_by_take(pool))
     }
 
-    // StringSlash = (order (one '\\') (or (one-in "tnr0") (one-any)))
-    fn on_string_slash(&mut self, a:char) -> JumpTo {
+    // Number = (order (opt '-') Digit (any (or Digit Letter (one-in "_.") (and (after "eE") (one-in "+-")))))
+    fn on_number(&mut self, a:char, pool:&mut Pool, diag:&mut Diag) -> JumpTo {
         match a {
-            't' => {self.take_raw_push('\t'); JumpTo::String}
-            'n' => {self.take_raw_push('\n'); JumpTo::String}
-            'r' => {self.take_raw_push('\r'); JumpTo::String}
-            '0' => {self.take_raw_push('\0'); JumpTo::String}
+            '0'..='9' | 'a'..='z' | 'A'..='Z' | '_' | '.' => {
+                self.take_raw_push(a);
+                JumpTo::Number
+            }
+            '+' | '-' if self.number_wants_sign() => {
+                self.take_raw_push(a);
+                JumpTo::Number
+            }
             _ => {
-                self.take_raw_push(a); JumpTo::String
+                if a > ' ' {
+                    self.back_one(a);
+                }
+                JumpTo::Take(self.take_number(pool, diag))
+            }
+        }
+    }
+    fn number_wants_sign(&self) -> bool {
+        let raw = self.take_raw.trim_start_matches('-');
+        let is_radix = raw.starts_with("0x") || raw.starts_with("0b") || raw.starts_with("0o");
+        !is_radix && (raw.ends_with('e') || raw.ends_with('E'))
+    }
+    fn take_number(&self, pool:&mut Pool, diag:&mut Diag) -> TokenValue {
+        match parse_number(&self.take_raw) {
+            Ok((true, digits, suffix)) => {
+                TokenValue::LiteralFloat(pool.add_str(digits), suffix)
+            }
+            Ok((false, digits, suffix)) => {
+                TokenValue::LiteralInteger(pool.add_str(digits), suffix)
+            }
+            Err(describe) => {
+                diag.push_error(self.take_span(), &describe);
+                TokenValue::LiteralInteger(pool.add_str("0"), NumberSuffix::None)
             }
         }
     }
 
-    // String = (order (one '"') (any-or StringSlash (not '"')) (one '"'))
-    fn on_string(&mut self, a:char) -> JumpTo {
+    // the String and Char are share the escapes, so go back to which one is by the quote
+    fn back_to_quote(&self) -> JumpTo {
+        if self.take_quote == '\'' {JumpTo::Char} else {JumpTo::String}
+    }
+    fn on_quote(&mut self, a:char, diag:&mut Diag) -> JumpTo {
+        if self.take_quote == '\'' {self.on_char(a, diag)} else {self.on_string(a, diag)}
+    }
+
+    // StringSlash = (order (one '\\') (or (one-in "tnr0\\\"'") StringUnicode))
+    fn on_string_slash(&mut self, a:char, diag:&mut Diag) -> JumpTo {
+        match a {
+            't' => {self.take_raw_push('\t'); self.back_to_quote()}
+            'n' => {self.take_raw_push('\n'); self.back_to_quote()}
+            'r' => {self.take_raw_push('\r'); self.back_to_quote()}
+            '0' => {self.take_raw_push('\0'); self.back_to_quote()}
+            '\\' | '\"' | '\'' => {self.take_raw_push(a); self.back_to_quote()}
+            'u' => {self.escape_raw.clear(); JumpTo::StringUnicode}
+            '\0' => {self.on_quote(a, diag)}
+            _ => {
+                self.error_at_now(diag, &format!("unknown escape '\\{}' in string", a.escape_default()));
+                self.take_raw_push(a); self.back_to_quote()
+            }
+        }
+    }
+
+    // StringUnicode = (order "u{" (one-to-many 6 HexDigit) '}')
+    fn on_string_unicode(&mut self, a:char, diag:&mut Diag) -> JumpTo {
+        let is_open = self.escape_raw.starts_with('{');
+        match a {
+            '{' if !is_open => {self.escape_raw.push(a); JumpTo::StringUnicode}
+            '}' if is_open => {
+                let hex = &self.escape_raw[1..];
+                let code = u32::from_str_radix(hex, 16).ok().filter(|_| hex.len() <= 6);
+                match code.and_then(char::from_u32) {
+                    Some(b) => {self.take_raw_push(b);}
+                    None => {
+                        let describe = format!("invalid unicode escape '\\u{{{}}}'", hex);
+                        self.error_at_now(diag, &describe);
+                        self.take_raw_push(char::REPLACEMENT_CHARACTER);
+                    }
+                }
+                self.back_to_quote()
+            }
+            _ if is_open && a.is_ascii_hexdigit() => {self.escape_raw.push(a); JumpTo::StringUnicode}
+            _ => {
+                self.error_at_now(diag, "invalid unicode escape, it should be like '\\u{1F600}'");
+                self.on_quote(a, diag)
+            }
+        }
+    }
+
+    // Char = (order (one '\'') (or StringSlash (not "'\n")) (one '\''))
+    fn on_char(&mut self, a:char, diag:&mut Diag) -> JumpTo {
         match a {
             '\\' => {JumpTo::StringSlash}
-            '\"' => {JumpTo::Take(self.take_string())}
+            '\'' => {self.now_are_token_end(); JumpTo::Take(self.take_char(diag))}
+            '\n' | '\0' => {
+                self.error_at(diag, self.take_start, self.take_start + 1, "missing closing ' of char literal");
+                self.back_one(a);
+                JumpTo::Take(TokenValue::LiteralChar(self.take_raw.chars().next().unwrap_or('\0')))
+            }
+            _ => {
+                self.take_raw_push(a); JumpTo::Char
+            }
+        }
+    }
+    fn take_char(&self, diag:&mut Diag) -> TokenValue {
+        let mut chars = self.take_raw.chars();
+        match (chars.next(), chars.next()) {
+            (Some(a), None) => {TokenValue::LiteralChar(a)}
+            (a, _) => {
+                diag.push_error(self.take_span(), "char should be one character, the string is \"...\"");
+                TokenValue::LiteralChar(a.unwrap_or('\0'))
+            }
+        }
+    }
+
+    // StringLeftBrace = (order (one '{') (or (one '{') (order Expr (one '}'))))
+    fn on_string_left_brace(&mut self, a:char, pool:&mut Pool, diag:&mut Diag) -> JumpTo {
+        match a {
+            '{' => {self.take_raw_push(a); JumpTo::String}
+            '}' => {
+                self.error_at_now(diag, "empty '{}' in string, use '{{' for a '{'");
+                JumpTo::String
+            }
+            '\0' => {self.on_string(a, diag)}
+            _ => {
+                self.back_one(a);
+                let (tokens, closed) = self.lex_format_expr(pool, diag);
+                self.take_text_piece();
+                self.take_pieces.push(FormatPiece::Expr(tokens));
+                match closed {
+                    // the string is end with the line, so the next lines are lexed as code
+                    '\n' | '\0' => {
+                        self.take_end = self.i;
+                        JumpTo::Take(self.take_string())
+                    }
+                    _ => {JumpTo::String}
+                }
+            }
+        }
+    }
+    // the Expr in string is lexed by the tokens until the '}' of it, the char closed it is returned
+    //   it's unclosed if the line or the string is end before, like "{" or "{x"
+    //   the string is read again from the end of last token, so the '"' can close it
+    fn lex_format_expr(&mut self, pool:&mut Pool, diag:&mut Diag) -> (Vec<Token>, char) {
+        let (outer_start, outer_end) = (self.take_start, self.take_end);
+        let outer_quote = self.take_quote;
+        let brace_at = self.brace_at;
+        let outer_raw = std::mem::take(&mut self.take_raw);
+        let outer_pieces = std::mem::take(&mut self.take_pieces);
+        let outer_last_end = std::mem::replace(&mut self.last_end, brace_at + 1);
+        let mut tokens = Vec::new();
+        let mut depth = 0usize;
+        let closed = loop {
+            let (next, at) = self.peek_format_expr();
+            if matches!(next, '\n' | '#' | '\0') || (next == '"' && depth == 0) {
+                self.error_at(diag, brace_at, brace_at + 1, "unclosed '{' in string");
+                self.i = at;
+                self.back_chars.clear();
+                break if next == '"' {next} else {'\n'}
+            }
+            let token = self.lexer_pass(pool, diag);
+            match token.val {
+                TokenValue::LP0 | TokenValue::LP1 | TokenValue::LP2 => {depth += 1}
+                TokenValue::RP2 if depth == 0 => {break '}'}
+                TokenValue::RP0 | TokenValue::RP1 | TokenValue::RP2 => {depth = depth.saturating_sub(1)}
+                _ => {}
+            }
+            tokens.push(token);
+        };
+        self.take_start = outer_start;
+        self.take_end = outer_end;
+        self.take_quote = outer_quote;
+        self.last_end = outer_last_end;
+        self.take_raw = outer_raw;
+        self.take_pieces = outer_pieces;
+        (tokens, closed)
+    }
+
+    // the first char after the last token in the Expr of string and it's offset, the '\0' is end of code
+    fn peek_format_expr(&self) -> (char, usize) {
+        let mut i = self.last_end;
+        while i < self.code.len() {
+            let (a, len) = decode_utf8(&self.code[i..]);
+            if !matches!(a, ' ' | '\t' | '\r') {
+                return (a, i)
+            }
+            i += len;
+        }
+        ('\0', i)
+    }
+
+    // StringRightBrace = (order (one '}') (one '}'))
+    fn on_string_right_brace(&mut self, a:char, diag:&mut Diag) -> JumpTo {
+        self.take_raw_push('}');
+        if a == '}' {
+            JumpTo::String
+        } else {
+            self.error_at_now(diag, "single '}' in string, use '}}' for a '}'");
+            self.on_string(a, diag)
+        }
+    }
+
+    // String = (order (one '"') (any-or StringSlash StringLeftBrace StringRightBrace (not '"')) (one '"'))
+    fn on_string(&mut self, a:char, diag:&mut Diag) -> JumpTo {
+        match a {
+            '\\' => {JumpTo::StringSlash}
+            '{' => {
+                self.brace_at = self.at;
+                JumpTo::StringLeftBrace
+            }
+            '}' => {JumpTo::StringRightBrace}
+            '\"' => {self.now_are_token_end(); JumpTo::Take(self.take_string())}
+            '\0' => {
+                self.error_at(diag, self.take_start, self.take_start + 1, "unterminated string, lack of '\"'");
+                self.now_are_token_end();
+                JumpTo::Take(self.take_string())
+            }
             _ => {
                 self.take_raw_push(a); JumpTo::String
             }
         }
     }
 
-    // Symbol2 = (or "<-")
+    // Symbol2 = (or "<-" "...")
     fn on_symbol2(&mut self, a:char) -> JumpTo {
-        match self.take_raw.as_str() {
-            "<" => {
-                match a {
-                    '-' => {JumpTo::Take(TokenValue::ASSIGN)}
-                    _ => {self.back_one(a); JumpTo::Take(TokenValue::LP3)}
-                }
+        match (self.take_raw.as_str(), a) {
+            ("<", '-') => {self.now_are_token_end(); JumpTo::Take(TokenValue::ASSIGN)}
+            ("<", _) => {self.back_one(a); JumpTo::Take(TokenValue::LP3)}
+            (".", '.') => {self.take_raw_push(a); JumpTo::Symbol2}
+            ("..", '.') => {self.now_are_token_end(); JumpTo::Take(TokenValue::ELLIPSIS)}
+            ("..", _) => {
+                // the ".." is two DOT, back the second '.' after a
+                self.back_one(a);
+                self.back_chars.push(('.', self.take_start + 1, self.take_start + 2));
+                self.take_end = self.take_start + 1;
+                JumpTo::Take(TokenValue::DOT)
             }
-            _ => {panic!("incorrect-logic: control-flow should can't to here")}
+            _ => {self.back_one(a); JumpTo::Take(TokenValue::DOT)}
         }
     }
 
-    fn on_first(&mut self, a:char) -> JumpTo {
+    fn on_first(&mut self, a:char, diag:&mut Diag) -> JumpTo {
         match a {
-            '(' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::LP0)}
-            ')' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::RP0)}
-            '[' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::LP1)}
-            ']' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::RP1)}
-            '{' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::LP2)}
-            '}' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::RP2)}
-            '<' => {self.now_are_token_loc().take_raw_push(a); JumpTo::Symbol2}
-            '>' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::RP3)}
-            '@' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::AT)}
-            ':' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::COLON)}
-            ',' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::COMMA)}
-            '.' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::DOT)}
-            '=' => {self.now_are_token_loc(); JumpTo::Take(TokenValue::EQUAL)}
-            '"' => {self.now_are_token_loc(); JumpTo::String}
+            '(' => {self.now_are_token_start(); JumpTo::Take(TokenValue::LP0)}
+            ')' => {self.now_are_token_start(); JumpTo::Take(TokenValue::RP0)}
+            '[' => {self.now_are_token_start(); JumpTo::Take(TokenValue::LP1)}
+            ']' => {self.now_are_token_start(); JumpTo::Take(TokenValue::RP1)}
+            '{' => {self.now_are_token_start(); JumpTo::Take(TokenValue::LP2)}
+            '}' => {self.now_are_token_start(); JumpTo::Take(TokenValue::RP2)}
+            '<' => {self.now_are_token_start().take_raw_push(a); JumpTo::Symbol2}
+            '>' => {self.now_are_token_start(); JumpTo::Take(TokenValue::RP3)}
+            '@' => {self.now_are_token_start(); JumpTo::Take(TokenValue::AT)}
+            ':' => {self.now_are_token_start(); JumpTo::Take(TokenValue::COLON)}
+            ',' => {self.now_are_token_start(); JumpTo::Take(TokenValue::COMMA)}
+            '.' => {self.now_are_token_start().take_raw_push(a); JumpTo::Symbol2}
+            '=' => {self.now_are_token_start(); JumpTo::Take(TokenValue::EQUAL)}
+            '"' => {self.now_are_token_start().take_quote = a; JumpTo::StringOpen}
+            '\'' => {self.now_are_token_start().take_quote = a; JumpTo::Char}
             '#' => {JumpTo::Comment}
+            '0'..='9' => {self.now_are_token_start().take_raw_push(a); JumpTo::Number}
             _ => {
-                if a > ' ' {
-                    self.now_are_token_loc().take_raw_push(a);
+                let is_id_start = if a.is_ascii() {a > ' '} else {is_unicode_id_start(a)};
+                if is_id_start {
+                    self.now_are_token_start().take_raw_push(a);
                     JumpTo::Id
                 } else {
+                    if !(a.is_ascii() || a.is_whitespace()) {
+                        self.error_bad_id_char(a, diag);
+                    }
                     JumpTo::First
                 }
             }
         }
     }
 
-    fn jump(&mut self, a:char, now:JumpTo, pool:&mut Pool) -> JumpTo {
+    fn jump(&mut self, a:char, now:JumpTo, pool:&mut Pool, diag:&mut Diag) -> JumpTo {
         match now {
-            JumpTo::First => {self.on_first(a)}
+            JumpTo::First => {self.on_first(a, diag)}
             JumpTo::Symbol2 => {self.on_symbol2(a)}
             JumpTo::Comment => {self.on_comment(a)}
-            JumpTo::Id => {self.on_id(a, pool)}
-            JumpTo::String => {self.on_string(a)}
-            JumpTo::StringSlash => {self.on_string_slash(a)}
+            JumpTo::Id => {self.on_id(a, pool, diag)}
+            JumpTo::Number => {self.on_number(a, pool, diag)}
+            JumpTo::String => {self.on_string(a, diag)}
+            JumpTo::StringOpen => {self.on_string_open(a, diag)}
+            JumpTo::StringQuote2 => {self.on_string_quote2(a)}
+            JumpTo::RawStringOpen => {self.on_raw_string_open(a, pool, diag)}
+            JumpTo::Verbatim => {self.on_verbatim(a, diag)}
+            JumpTo::StringSlash => {self.on_string_slash(a, diag)}
+            JumpTo::StringUnicode => {self.on_string_unicode(a, diag)}
+            JumpTo::Char => {self.on_char(a, diag)}
+            JumpTo::StringLeftBrace => {self.on_string_left_brace(a, pool, diag)}
+            JumpTo::StringRightBrace => {self.on_string_right_brace(a, diag)}
             _ => {now}
         }
     }
 
-    fn get_char_by_pass(&mut self) -> char {
-        let a = self.code[self.i] as char;
-        self.i += 1;
-        self.col += 1;
-        if a == '\n' {
-            self.row += 1;
-            self.col = 0;
+    // the bad char (invalid UTF-8, control) will report and replace, so lexer can go on
+    fn get_char_by_pass(&mut self, diag:&mut Diag) -> char {
+        self.at = self.i;
+        if let Some(&(start, len)) = self.bad_utf8.first() {
+            if start == self.i {
+                self.bad_utf8.remove(0);
+                self.i += len;
+                self.at_end = self.i;
+                self.error_at_now(diag, &format!("invalid UTF-8 sequence {:02X?}", &self.code[start..start+len]));
+                return char::REPLACEMENT_CHARACTER
+            }
+        }
+        let (a, len) = decode_utf8(&self.code[self.i..]);
+        self.i += len;
+        self.at_end = self.i;
+        if a.is_ascii_control() && !matches!(a, '\t' | '\n' | '\r') {
+            self.error_at_now(diag, &format!("stray control character U+{:04X}", a as u32));
+            return ' '
         }
         a
     }
@@ -218,25 +674,138 @@ impl Object {
 impl Lexer for Object {
     fn lexer_pass(&mut self, pool:&mut Pool, diag:&mut Diag) -> Token {
         let mut to = JumpTo::First;
-        self.now_are_token_loc().reset_take_raw();
-        if self.has_back() {
-            let a = self.pop_back();
-            to = self.jump(a, to, pool);
+        self.now_are_token_start().reset_take_raw();
+        while let Some(a) = self.pop_back() {
+            to = self.jump(a, to, pool, diag);
             if let JumpTo::Take(val) = to {
-                return Token { loc: self.take_loc.clone(), val }
+                return self.take_token(val)
             }
         }
         while self.i < self.code.len() {
-            let a = self.get_char_by_pass();
-            to = self.jump(a, to, pool);
+            let a = self.get_char_by_pass(diag);
+            to = self.jump(a, to, pool, diag);
             if let JumpTo::Take(val) = to {
-                return Token { loc: self.take_loc.clone(), val }
+                return self.take_token(val)
             }
         }
-        if let JumpTo::Take(val) = self.jump('\0', to, pool) {
-            Token { loc: self.take_loc.clone(), val }
+        self.at = self.code.len();
+        self.at_end = self.code.len();
+        if let JumpTo::Take(val) = self.jump('\0', to, pool, diag) {
+            self.take_token(val)
         } else {
-            Token { loc: self.take_loc.clone(), val: TokenValue::_EOF }
+            self.now_are_token_start();
+            self.take_token(TokenValue::_EOF)
+        }
+    }
+}
+
+// The first char of code and it's length by bytes, the code should start with valid UTF-8
+fn decode_utf8(code:&[u8]) -> (char, usize) {
+    let len = match code[0] {
+        0x00..=0x7F => {1}
+        0xC0..=0xDF => {2}
+        0xE0..=0xEF => {3}
+        _ => {4}
+    };
+    let len = len.min(code.len());
+    match std::str::from_utf8(&code[..len]).ok().and_then(|a| a.chars().next()) {
+        Some(a) => {(a, len)}
+        None => {(char::REPLACEMENT_CHARACTER, len)}
+    }
+}
+
+// The text of TripleString without the first and last blank lines, and the common indent of lines
+fn strip_indent(text:&str) -> String {
+    let mut lines:Vec<&str> = text.split('\n').map(|a| a.strip_suffix('\r').unwrap_or(a)).collect();
+    if lines.len() > 1 && lines[0].trim().is_empty() {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines[lines.len()-1].trim().is_empty() {
+        lines.pop();
+    }
+    let indent_of = |a:&str| a.len() - a.trim_start_matches([' ', '\t']).len();
+    let indent = lines.iter().filter(|a| !a.trim().is_empty()).map(|a| indent_of(a)).min().unwrap_or(0);
+    let lines:Vec<&str> = lines.iter().map(|a| if a.trim().is_empty() {""} else {&a[indent..]}).collect();
+    lines.join("\n")
+}
+
+// The non-ASCII part of Id, use the XID_Start and XID_Continue properties of Unicode (UAX #31)
+fn is_unicode_id_start(a:char) -> bool {
+    unicode_id::is_xid_start(a)
+}
+fn is_unicode_id_continue(a:char) -> bool {
+    unicode_id::is_xid_continue(a)
+}
+
+// Parse the raw text of Number to (is-float, canonical-digits, suffix)
+//   Integer = (order (opt '-') (or (order "0x" HexDigits) (order "0b" BinDigits) (order "0o" OctDigits) Digits) Suffix)
+//   Float = (order (opt '-') Digits (opt (order '.' Digits)) (opt (order (one-in "eE") (opt (one-in "+-")) Digits)) Suffix)
+fn parse_number(raw:&str) -> Result<(bool, String, NumberSuffix), String> {
+    let (negative, body) = match raw.strip_prefix('-') {
+        Some(a) => {(true, a)}
+        None => {(false, raw)}
+    };
+    let (radix, body) = match body.get(0..2) {
+        Some("0x") => {(16, &body[2..])}
+        Some("0b") => {(2, &body[2..])}
+        Some("0o") => {(8, &body[2..])}
+        _ => {(10, body)}
+    };
+    let digits_end = |s:&str, f:&dyn Fn(char) -> bool| -> usize {
+        s.find(|a:char| !(f(a) || a == '_')).unwrap_or(s.len())
+    };
+    let has_digit = |s:&str| s.chars().any(|a| a != '_');
+    let mut is_float = false;
+    let mut i = digits_end(body, &|a| a.is_digit(radix));
+    if !has_digit(&body[..i]) || body.starts_with('_') {
+        return Err(format!("number '{}' lack of digits", raw))
+    }
+    if radix == 10 {
+        if body[i..].starts_with('.') {
+            let n = digits_end(&body[i+1..], &|a| a.is_ascii_digit());
+            if !has_digit(&body[i+1..i+1+n]) {
+                return Err(format!("number '{}' lack of digits after '.'", raw))
+            }
+            is_float = true;
+            i += 1 + n;
+        }
+        if body[i..].starts_with('e') || body[i..].starts_with('E') {
+            let mut j = i + 1;
+            if body[j..].starts_with('+') || body[j..].starts_with('-') {
+                j += 1;
+            }
+            let n = digits_end(&body[j..], &|a| a.is_ascii_digit());
+            if !has_digit(&body[j..j+n]) {
+                return Err(format!("number '{}' lack of digits in exponent", raw))
+            }
+            is_float = true;
+            i = j + n;
+        }
+    }
+    let digits:String = body[..i].chars().filter(|&a| a != '_').collect();
+    let suffix = match NumberSuffix::from_name(&body[i..]) {
+        Some(a) => {a}
+        None => {return Err(format!("number '{}' has invalid suffix '{}'", raw, &body[i..]))}
+    };
+    if is_float || suffix.is_float() {
+        if radix != 10 {
+            return Err(format!("number '{}' can't be float with radix {}", raw, radix))
+        }
+        if !(suffix == NumberSuffix::None || suffix.is_float()) {
+            return Err(format!("number '{}' is float but suffix '{}' is integer", raw, suffix.name()))
+        }
+        let sign = if negative {"-"} else {""};
+        return Ok((true, format!("{}{}", sign, digits), suffix))
+    }
+    let value = match u128::from_str_radix(&digits, radix) {
+        Ok(a) if a <= i128::MAX as u128 => {a as i128}
+        _ => {return Err(format!("number '{}' is too large", raw))}
+    };
+    let value = if negative {-value} else {value};
+    if let Some((min, max)) = suffix.integer_range() {
+        if value < min || value > max {
+            return Err(format!("number '{}' out of range for '{}' ({}..={})", raw, suffix.name(), min, max))
         }
     }
+    Ok((false, value.to_string(), suffix))
 }
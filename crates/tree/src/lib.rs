@@ -20,35 +20,110 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use core::fmt;
-use farvm_utils::{Diag, Pool,pool, Emit};
+use std::marker::PhantomData;
+
+use farvm_utils::{Diag, Pool,pool, Emit, Span, Source, source::FileId};
 
 mod lexer_v1;
 mod parser_v1;
+mod macro_v1;
+mod json_v1;
+mod unicode_id;
+
+pub mod token_stream;
+pub mod visit;
+pub type TokenStream<'a, L> = token_stream::Object<'a, L>;
+pub use visit::{Visitor, Folder};
 
-#[derive(Clone,Debug)]
-pub struct TokenLoc {
-    row:u32,
-    col:u32,
+// The width written after a number literal, like the 'i32' in '1i32'
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum NumberSuffix {
+    None,
+    I8, I16, I32, I64,
+    U8, U16, U32, U64,
+    F32, F64,
 }
-impl fmt::Display for TokenLoc {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{}", self.row, self.col)
+
+impl NumberSuffix {
+    pub fn from_name(name:&str) -> Option<NumberSuffix> {
+        match name {
+            "" => {Some(NumberSuffix::None)}
+            "i8" => {Some(NumberSuffix::I8)}
+            "i16" => {Some(NumberSuffix::I16)}
+            "i32" => {Some(NumberSuffix::I32)}
+            "i64" => {Some(NumberSuffix::I64)}
+            "u8" => {Some(NumberSuffix::U8)}
+            "u16" => {Some(NumberSuffix::U16)}
+            "u32" => {Some(NumberSuffix::U32)}
+            "u64" => {Some(NumberSuffix::U64)}
+            "f32" => {Some(NumberSuffix::F32)}
+            "f64" => {Some(NumberSuffix::F64)}
+            _ => {None}
+        }
     }
-}
-impl Default for TokenLoc {
-    fn default() -> Self {
-        TokenLoc { row: 1, col: 0 }
+    pub fn name(&self) -> &'static str {
+        match self {
+            NumberSuffix::None => {""}
+            NumberSuffix::I8 => {"i8"}
+            NumberSuffix::I16 => {"i16"}
+            NumberSuffix::I32 => {"i32"}
+            NumberSuffix::I64 => {"i64"}
+            NumberSuffix::U8 => {"u8"}
+            NumberSuffix::U16 => {"u16"}
+            NumberSuffix::U32 => {"u32"}
+            NumberSuffix::U64 => {"u64"}
+            NumberSuffix::F32 => {"f32"}
+            NumberSuffix::F64 => {"f64"}
+        }
+    }
+    pub fn is_float(&self) -> bool {
+        matches!(self, NumberSuffix::F32 | NumberSuffix::F64)
+    }
+    // the (min, max) of value, None for no-suffix and floats
+    pub fn integer_range(&self) -> Option<(i128, i128)> {
+        match self {
+            NumberSuffix::I8 => {Some((i8::MIN as i128, i8::MAX as i128))}
+            NumberSuffix::I16 => {Some((i16::MIN as i128, i16::MAX as i128))}
+            NumberSuffix::I32 => {Some((i32::MIN as i128, i32::MAX as i128))}
+            NumberSuffix::I64 => {Some((i64::MIN as i128, i64::MAX as i128))}
+            NumberSuffix::U8 => {Some((0, u8::MAX as i128))}
+            NumberSuffix::U16 => {Some((0, u16::MAX as i128))}
+            NumberSuffix::U32 => {Some((0, u32::MAX as i128))}
+            NumberSuffix::U64 => {Some((0, u64::MAX as i128))}
+            _ => {None}
+        }
     }
 }
 
+// A piece of string literal with "{expr}", like "Number:{a}" is [Text("Number:"), Expr([a])]
 #[derive(Clone,Debug,PartialEq,Eq)]
+pub enum FormatPiece {
+    Text(String),
+    Expr(Vec<Token>),
+}
+
+impl Emit for FormatPiece {
+    fn emit(&self, pool:&Pool) -> String {
+        match self {
+            FormatPiece::Text(a) => {format!("\"{}\"", a)}
+            FormatPiece::Expr(a) => {
+                let tokens:Vec<String> = a.iter().map(|t| t.val.emit(pool)).collect();
+                format!("{{{}}}", tokens.join(" "))
+            }
+        }
+    }
+}
+
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
 #[allow(dead_code)]
 pub enum TokenValue {
-    LiteralInteger(pool::Id),
-    LiteralFloat(pool::Id),
+    LiteralInteger(pool::Id, NumberSuffix),     // pool-str is decimal digits, like "-255"
+    LiteralFloat(pool::Id, NumberSuffix),       // pool-str is digits without '_' and suffix
     LiteralString(String),
+    LiteralFormat(Vec<FormatPiece>),            // string with "{expr}"
+    LiteralChar(char),
     Id(pool::Id),
+    MacroId(pool::Id),                          // name!, pool-str is the name without '!'
     LP0,                    // (
     RP0,                    // )
     LP1,                    // [
@@ -58,10 +133,12 @@ pub enum TokenValue {
     LP3,                    // <
     RP3,                    // >
     ASSIGN,                 // <-
+    ARROW,                  // ->
     AT,                     // @
     COLON,                  // :
     COMMA,                  // ,
     DOT,                    // .
+    ELLIPSIS,               // ...
     EQUAL,                  // =
     TILDE,                  // ~
     QUOTE1,                 // '
@@ -71,88 +148,426 @@ pub enum TokenValue {
     KeyNil,                 // nil
     KeyTrue,                // true
     KeyFalse,               // false
+    KeyModule,              // module
+    KeyImport,              // import
+    KeyPub,                 // pub
+    KeyAs,                  // as
+    #[default]
     _EOF,
 }
 
-impl Default for TokenValue {
-    fn default() -> Self {
-        TokenValue::_EOF
+impl TokenValue {
+    // same kind is same variant, the value in it isn't compared
+    pub fn is_kind(&self, kind:&TokenValue) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(kind)
+    }
+    // the name of kind for messages
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            TokenValue::LiteralInteger(..) => {"integer"}
+            TokenValue::LiteralFloat(..) => {"float"}
+            TokenValue::LiteralString(..) => {"string"}
+            TokenValue::LiteralFormat(..) => {"string"}
+            TokenValue::LiteralChar(..) => {"char"}
+            TokenValue::Id(..) => {"identifier"}
+            TokenValue::MacroId(..) => {"macro"}
+            TokenValue::LP0 => {"'('"}
+            TokenValue::RP0 => {"')'"}
+            TokenValue::LP1 => {"'['"}
+            TokenValue::RP1 => {"']'"}
+            TokenValue::LP2 => {"'{'"}
+            TokenValue::RP2 => {"'}'"}
+            TokenValue::LP3 => {"'<'"}
+            TokenValue::RP3 => {"'>'"}
+            TokenValue::ASSIGN => {"'<-'"}
+            TokenValue::ARROW => {"'->'"}
+            TokenValue::AT => {"'@'"}
+            TokenValue::COLON => {"':'"}
+            TokenValue::COMMA => {"','"}
+            TokenValue::DOT => {"'.'"}
+            TokenValue::ELLIPSIS => {"'...'"}
+            TokenValue::EQUAL => {"'='"}
+            TokenValue::TILDE => {"'~'"}
+            TokenValue::QUOTE1 => {"'\''"}
+            TokenValue::QUOTE2 => {"'\"'"}
+            TokenValue::KeyAuto => {"'auto'"}
+            TokenValue::KeyFor => {"'for'"}
+            TokenValue::KeyNil => {"'nil'"}
+            TokenValue::KeyTrue => {"'true'"}
+            TokenValue::KeyFalse => {"'false'"}
+            TokenValue::KeyModule => {"'module'"}
+            TokenValue::KeyImport => {"'import'"}
+            TokenValue::KeyPub => {"'pub'"}
+            TokenValue::KeyAs => {"'as'"}
+            TokenValue::_EOF => {"end of file"}
+        }
     }
 }
 
 impl Emit for TokenValue {
     fn emit(&self, pool:&Pool) -> String {
         match self {
-            TokenValue::LiteralInteger(a) => {
-                format!("(Integer {})", pool.str_at(*a))
+            TokenValue::LiteralInteger(a, suffix) => {
+                format!("(Integer {}{})", pool.str_at(*a), suffix.name())
             }
-            TokenValue::LiteralFloat(a) => {
-                format!("(Float {})", pool.str_at(*a))
+            TokenValue::LiteralFloat(a, suffix) => {
+                format!("(Float {}{})", pool.str_at(*a), suffix.name())
             }
             TokenValue::LiteralString(a) => {
                 format!("(String \"{}\")", a)
             }
+            TokenValue::LiteralChar(a) => {
+                format!("(Char '{}')", a.escape_debug())
+            }
+            TokenValue::LiteralFormat(a) => {
+                let pieces:Vec<String> = a.iter().map(|p| p.emit(pool)).collect();
+                format!("(Format {})", pieces.join(" "))
+            }
             TokenValue::Id(a) => {
                 format!("(Id {})", pool.str_at(*a))
             }
+            TokenValue::MacroId(a) => {
+                format!("(MacroId {})", pool.str_at(*a))
+            }
             //
-            TokenValue::LP0 => {format!("( LP0")}
-            TokenValue::RP0 => {format!(") RP0")}
-            TokenValue::LP1 => {format!("[ LP1")}
-            TokenValue::RP1 => {format!("] RP1")}
-            TokenValue::LP2 => {format!("{{ LP2")}
-            TokenValue::RP2 => {format!("}} RP2")}
-            TokenValue::LP3 => {format!("< LP3")}
-            TokenValue::RP3 => {format!("> RP3")}
-            TokenValue::ASSIGN => {format!("<- ASSIGN")}
-            TokenValue::AT => {format!("@ AT")}
-            TokenValue::COLON => {format!(": COLON")}
-            TokenValue::COMMA => {format!(", COMMA")}
-            TokenValue::DOT => {format!(". DOT")}
-            TokenValue::EQUAL => {format!("= EQUAL")}
-            TokenValue::TILDE => {format!("~ TILDE")}
-            TokenValue::QUOTE1 => {format!("' QUOTE1")}
-            TokenValue::QUOTE2 => {format!("\" QUOTE2")}
+            TokenValue::LP0 => {String::from("( LP0")}
+            TokenValue::RP0 => {String::from(") RP0")}
+            TokenValue::LP1 => {String::from("[ LP1")}
+            TokenValue::RP1 => {String::from("] RP1")}
+            TokenValue::LP2 => {String::from("{ LP2")}
+            TokenValue::RP2 => {String::from("} RP2")}
+            TokenValue::LP3 => {String::from("< LP3")}
+            TokenValue::RP3 => {String::from("> RP3")}
+            TokenValue::ASSIGN => {String::from("<- ASSIGN")}
+            TokenValue::ARROW => {String::from("-> ARROW")}
+            TokenValue::AT => {String::from("@ AT")}
+            TokenValue::COLON => {String::from(": COLON")}
+            TokenValue::COMMA => {String::from(", COMMA")}
+            TokenValue::DOT => {String::from(". DOT")}
+            TokenValue::ELLIPSIS => {String::from("... ELLIPSIS")}
+            TokenValue::EQUAL => {String::from("= EQUAL")}
+            TokenValue::TILDE => {String::from("~ TILDE")}
+            TokenValue::QUOTE1 => {String::from("' QUOTE1")}
+            TokenValue::QUOTE2 => {String::from("\" QUOTE2")}
             //
-            TokenValue::KeyAuto => {format!("auto")}
-            TokenValue::KeyFor => {format!("for")}
-            TokenValue::KeyNil => {format!("nil")}
-            TokenValue::KeyTrue => {format!("true")}
-            TokenValue::KeyFalse => {format!("false")}
+            TokenValue::KeyAuto => {String::from("auto")}
+            TokenValue::KeyFor => {String::from("for")}
+            TokenValue::KeyNil => {String::from("nil")}
+            TokenValue::KeyTrue => {String::from("true")}
+            TokenValue::KeyFalse => {String::from("false")}
+            TokenValue::KeyModule => {String::from("module")}
+            TokenValue::KeyImport => {String::from("import")}
+            TokenValue::KeyPub => {String::from("pub")}
+            TokenValue::KeyAs => {String::from("as")}
             //
-            TokenValue::_EOF => {format!("<!EOF!>")}
+            TokenValue::_EOF => {String::from("<!EOF!>")}
         }
     }
 }
 
-#[derive(Clone,Debug,Default)]
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    Comment,                // # ...
+    DocComment,             // ## ...
+    Skipped,                // the bad chars reported by lexer
+}
+
+// The code before a token but isn't token, it's only kept by lossless lexer
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Trivia {
+    pub kind:TriviaKind,
+    pub span:Span,
+}
+
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
 pub struct Token {
-    loc:TokenLoc,
-    val:TokenValue,
+    pub span:Span,
+    pub val:TokenValue,
+    pub trivia:Vec<Trivia>,
+    pub line_start:bool,        // it's at the column 1 of a line, the parser resync at the define there
+}
+
+impl Token {
+    // the span with leading trivia, the full spans of all tokens until _EOF are cover the code
+    pub fn full_span(&self) -> Span {
+        match self.trivia.first() {
+            Some(a) => {a.span.to(self.span)}
+            None => {self.span}
+        }
+    }
 }
 
 impl Emit for Token {
     fn emit(&self, pool:&Pool) -> String {
-        format!("[{} {}]", self.loc, self.val.emit(pool))
+        format!("[{} {}]", self.span, self.val.emit(pool))
     }
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
+#[derive(Clone,Debug,PartialEq)]
+pub enum Literal {
+    Integer(pool::Id, NumberSuffix),
+    Float(pool::Id, NumberSuffix),
+    String(String),
+    Char(char),
+    Bool(bool),
+    Nil,
+}
+
+impl Emit for Literal {
+    fn emit(&self, pool:&Pool) -> String {
+        match self {
+            Literal::Integer(a, suffix) => {format!("{}{}", pool.str_at(*a), suffix.name())}
+            Literal::Float(a, suffix) => {format!("{}{}", pool.str_at(*a), suffix.name())}
+            Literal::String(a) => {format!("\"{}\"", a.escape_debug())}
+            Literal::Char(a) => {format!("'{}'", a.escape_debug())}
+            Literal::Bool(a) => {format!("{}", a)}
+            Literal::Nil => {String::from("nil")}
+        }
+    }
+}
+
+#[derive(Clone,Debug,Default,PartialEq)]
 pub enum Node {
-    Define{loc:TokenLoc,},
+    Module{span:Span, items:Vec<Node>},
+    Define{span:Span, name:pool::Id, generics:Vec<Node>, params:Vec<Node>, body:Box<Node>},  // (name<generics> params) = body
+    Param{span:Span, name:pool::Id, ty:Option<Box<Node>>},                  // name:Type
+    TypeParam{span:Span, name:pool::Id},                                    // T in (map<T> ...)
+    TypeRef{span:Span, name:pool::Id},                                      // Number
+    AutoType{span:Span},                                                    // auto, the type is inferred
+    Generic{span:Span, base:Box<Node>, args:Vec<Node>},                     // List<T>
+    FunctionType{span:Span, params:Vec<Node>, ret:Box<Node>},              // (T U -> V)
+    DispatchGroup{span:Span, strategy:pool::Id, name:pool::Id, variadic:bool, methods:Vec<Node>},  // for apply (name ...) {methods}
+    Call{span:Span, callee:Box<Node>, args:Vec<Node>},                      // (f a b)
+    MacroCall{span:Span, name:pool::Id, args:Vec<Node>},                    // (name! a b)
+    MacroDefine{span:Span, name:pool::Id, params:Vec<Node>, variadic:bool, body:Box<Node>},  // (name! params ...) = body
+    Block{span:Span, items:Vec<Node>},                                      // { a b }
+    List{span:Span, items:Vec<Node>},                                       // [a b]
+    Literal{span:Span, value:Literal},
+    Format{span:Span, parts:Vec<Node>},                                     // "text{expr}"
+    Ident{span:Span, name:pool::Id},
+    MemberAccess{span:Span, object:Box<Node>, member:pool::Id},             // object.member
+    ObjectLiteral{span:Span, parent:Option<Box<Node>>, slots:Vec<Node>},    // @parent{slots}
+    Slot{span:Span, name:pool::Id, value:Box<Node>},                        // name: value
+    Assign{span:Span, name:pool::Id, ty:Option<Box<Node>>, value:Box<Node>},  // name:Type = value
+    Rebind{span:Span, target:Box<Node>, value:Box<Node>},                   // target <- value
+    ModuleDecl{span:Span, path:pool::Id},                                   // module geo.shapes
+    Import{span:Span, path:pool::Id, alias:Option<pool::Id>, names:Vec<Node>},  // import geo.shapes as s {area}
+    Public{span:Span, item:Box<Node>},                                      // pub item
+    Error{span:Span},                                                       // the bad part is reported to Diag
+    #[default]
     _Empty,
 }
 
-impl Default for Node {
-    fn default() -> Self {
-        Node::_Empty
+// The identity of node by it's address, it's only made by Node::id and lives in the borrow of tree
+//   so the tree can't be moved or changed while the ids are kept, the id of a clone is another
+//   the span isn't identity, the nodes from one macro template have same span
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct NodeId<'t>(usize, PhantomData<&'t Node>);
+
+impl Node {
+    pub fn id(&self) -> NodeId<'_> {
+        NodeId(self as *const Node as usize, PhantomData)
+    }
+
+    // the _Empty isn't from source, it's span is default
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Module{span, ..} => {*span}
+            Node::Define{span, ..} => {*span}
+            Node::Param{span, ..} => {*span}
+            Node::TypeParam{span, ..} => {*span}
+            Node::TypeRef{span, ..} => {*span}
+            Node::AutoType{span} => {*span}
+            Node::FunctionType{span, ..} => {*span}
+            Node::Generic{span, ..} => {*span}
+            Node::DispatchGroup{span, ..} => {*span}
+            Node::Call{span, ..} => {*span}
+            Node::MacroCall{span, ..} => {*span}
+            Node::MacroDefine{span, ..} => {*span}
+            Node::Block{span, ..} => {*span}
+            Node::List{span, ..} => {*span}
+            Node::Literal{span, ..} => {*span}
+            Node::Format{span, ..} => {*span}
+            Node::Ident{span, ..} => {*span}
+            Node::MemberAccess{span, ..} => {*span}
+            Node::ObjectLiteral{span, ..} => {*span}
+            Node::Slot{span, ..} => {*span}
+            Node::Assign{span, ..} => {*span}
+            Node::Rebind{span, ..} => {*span}
+            Node::ModuleDecl{span, ..} => {*span}
+            Node::Import{span, ..} => {*span}
+            Node::Public{span, ..} => {*span}
+            Node::Error{span} => {*span}
+            Node::_Empty => {Span::default()}
+        }
+    }
+
+    // the nodes in it, the names (like Define.name) and the Import.names are not
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Node::Module{items, ..} | Node::Block{items, ..} | Node::List{items, ..} => {items.iter().collect()}
+            Node::Define{generics, params, body, ..} => {
+                generics.iter().chain(params.iter()).chain(std::iter::once(body.as_ref())).collect()
+            }
+            Node::Param{ty, ..} => {ty.iter().map(|a| a.as_ref()).collect()}
+            Node::Generic{base, args, ..} => {std::iter::once(base.as_ref()).chain(args.iter()).collect()}
+            Node::FunctionType{params, ret, ..} => {params.iter().chain(std::iter::once(ret.as_ref())).collect()}
+            Node::DispatchGroup{methods, ..} => {methods.iter().collect()}
+            Node::Call{callee, args, ..} => {std::iter::once(callee.as_ref()).chain(args.iter()).collect()}
+            Node::MacroCall{args, ..} => {args.iter().collect()}
+            Node::MacroDefine{params, body, ..} => {params.iter().chain(std::iter::once(body.as_ref())).collect()}
+            Node::Format{parts, ..} => {parts.iter().collect()}
+            Node::MemberAccess{object, ..} => {vec![object.as_ref()]}
+            Node::ObjectLiteral{parent, slots, ..} => {parent.iter().map(|a| a.as_ref()).chain(slots.iter()).collect()}
+            Node::Slot{value, ..} => {vec![value.as_ref()]}
+            Node::Assign{ty, value, ..} => {ty.iter().map(|a| a.as_ref()).chain(std::iter::once(value.as_ref())).collect()}
+            Node::Rebind{target, value, ..} => {vec![target.as_ref(), value.as_ref()]}
+            Node::Public{item, ..} => {vec![item.as_ref()]}
+            Node::TypeParam{..} | Node::TypeRef{..} | Node::AutoType{..} | Node::Literal{..} | Node::Ident{..} | Node::Error{..} | Node::_Empty
+            | Node::ModuleDecl{..} | Node::Import{..} => {
+                Vec::new()
+            }
+        }
+    }
+
+    // the node with each child mapped by f, the children in items can be spliced by f returns many nodes
+    //   the child not in items is the only one of f returns, or a List of them
+    pub fn map_children(self, f:&mut dyn FnMut(Node) -> Vec<Node>) -> Node {
+        match self {
+            Node::Module{span, items: a} => {Node::Module{span, items: map_items(a, f)}}
+            Node::Define{span, name, generics, params, body} => {
+                Node::Define{span, name, generics: map_items(generics, f), params: map_items(params, f), body: map_one(*body, f)}
+            }
+            Node::Param{span, name, ty} => {Node::Param{span, name, ty: ty.map(|a| map_one(*a, f))}}
+            Node::Generic{span, base, args} => {Node::Generic{span, base: map_one(*base, f), args: map_items(args, f)}}
+            Node::FunctionType{span, params, ret} => {Node::FunctionType{span, params: map_items(params, f), ret: map_one(*ret, f)}}
+            Node::DispatchGroup{span, strategy, name, variadic, methods} => {
+                Node::DispatchGroup{span, strategy, name, variadic, methods: map_items(methods, f)}
+            }
+            Node::Call{span, callee, args} => {Node::Call{span, callee: map_one(*callee, f), args: map_items(args, f)}}
+            Node::MacroCall{span, name, args} => {Node::MacroCall{span, name, args: map_items(args, f)}}
+            Node::MacroDefine{span, name, params, variadic, body} => {
+                Node::MacroDefine{span, name, params: map_items(params, f), variadic, body: map_one(*body, f)}
+            }
+            Node::Block{span, items: a} => {Node::Block{span, items: map_items(a, f)}}
+            Node::List{span, items: a} => {Node::List{span, items: map_items(a, f)}}
+            Node::Format{span, parts} => {Node::Format{span, parts: map_items(parts, f)}}
+            Node::MemberAccess{span, object, member} => {Node::MemberAccess{span, object: map_one(*object, f), member}}
+            Node::ObjectLiteral{span, parent, slots} => {
+                Node::ObjectLiteral{span, parent: parent.map(|a| map_one(*a, f)), slots: map_items(slots, f)}
+            }
+            Node::Slot{span, name, value} => {Node::Slot{span, name, value: map_one(*value, f)}}
+            Node::Assign{span, name, ty, value} => {
+                Node::Assign{span, name, ty: ty.map(|a| map_one(*a, f)), value: map_one(*value, f)}
+            }
+            Node::Rebind{span, target, value} => {Node::Rebind{span, target: map_one(*target, f), value: map_one(*value, f)}}
+            Node::Public{span, item} => {Node::Public{span, item: map_one(*item, f)}}
+            Node::TypeParam{..} | Node::TypeRef{..} | Node::AutoType{..} | Node::Literal{..} | Node::Ident{..} | Node::Error{..} | Node::_Empty
+            | Node::ModuleDecl{..} | Node::Import{..} => {
+                self
+            }
+        }
+    }
+
+    // the S-expression with 2 spaces indent for each depth, the items of Module, Block, DispatchGroup are in lines
+    fn emit_at(&self, pool:&Pool, depth:usize) -> String {
+        let inline = |nodes:&[Node]| -> String {
+            nodes.iter().map(|a| format!(" {}", a.emit_at(pool, depth))).collect()
+        };
+        let lines = |nodes:&[Node]| -> String {
+            let indent = "  ".repeat(depth + 1);
+            nodes.iter().map(|a| format!("\n{}{}", indent, a.emit_at(pool, depth + 1))).collect()
+        };
+        match self {
+            Node::Module{items, ..} => {format!("(module{})", lines(items))}
+            Node::Define{name, generics, params, body, ..} => {
+                let generics = match generics.is_empty() {
+                    true => {String::new()}
+                    false => {format!("<{}>", inline(generics).trim_start())}
+                };
+                format!("(define ({}{}{}) {})", pool.str_at(*name), generics, inline(params), body.emit_at(pool, depth))
+            }
+            Node::Param{name, ty, ..} => {
+                match ty {
+                    Some(ty) => {format!("{}:{}", pool.str_at(*name), ty.emit_at(pool, depth))}
+                    None => {pool.str_at(*name)}
+                }
+            }
+            Node::TypeParam{name, ..} => {pool.str_at(*name)}
+            Node::TypeRef{name, ..} => {pool.str_at(*name)}
+            Node::AutoType{..} => {String::from("auto")}
+            Node::FunctionType{params, ret, ..} => {format!("(->{} {})", inline(params), ret.emit_at(pool, depth))}
+            Node::Generic{base, args, ..} => {format!("(generic {}{})", base.emit_at(pool, depth), inline(args))}
+            Node::DispatchGroup{strategy, name, variadic, methods, ..} => {
+                let dots = if *variadic {" ..."} else {""};
+                format!("(dispatch {} ({}{}){})", pool.str_at(*strategy), pool.str_at(*name), dots, lines(methods))
+            }
+            Node::Call{callee, args, ..} => {format!("(call {}{})", callee.emit_at(pool, depth), inline(args))}
+            Node::MacroCall{name, args, ..} => {format!("(macro {}!{})", pool.str_at(*name), inline(args))}
+            Node::MacroDefine{name, params, variadic, body, ..} => {
+                let dots = if *variadic {" ..."} else {""};
+                format!("(define-macro ({}!{}{}) {})", pool.str_at(*name), inline(params), dots, body.emit_at(pool, depth))
+            }
+            Node::Block{items, ..} => {format!("(block{})", lines(items))}
+            Node::List{items, ..} => {format!("(list{})", inline(items))}
+            Node::Literal{value, ..} => {value.emit(pool)}
+            Node::Format{parts, ..} => {format!("(format{})", inline(parts))}
+            Node::Ident{name, ..} => {pool.str_at(*name)}
+            Node::MemberAccess{object, member, ..} => {
+                format!("(. {} {})", object.emit_at(pool, depth), pool.str_at(*member))
+            }
+            Node::ObjectLiteral{parent, slots, ..} => {
+                match parent {
+                    Some(parent) => {format!("(object @{}{})", parent.emit_at(pool, depth), inline(slots))}
+                    None => {format!("(object{})", inline(slots))}
+                }
+            }
+            Node::Slot{name, value, ..} => {format!("(slot {} {})", pool.str_at(*name), value.emit_at(pool, depth))}
+            Node::Assign{name, ty, value, ..} => {
+                match ty {
+                    Some(ty) => {format!("(= {}:{} {})", pool.str_at(*name), ty.emit_at(pool, depth), value.emit_at(pool, depth))}
+                    None => {format!("(= {} {})", pool.str_at(*name), value.emit_at(pool, depth))}
+                }
+            }
+            Node::Rebind{target, value, ..} => {
+                format!("(<- {} {})", target.emit_at(pool, depth), value.emit_at(pool, depth))
+            }
+            Node::ModuleDecl{path, ..} => {format!("(module-name {})", pool.str_at(*path))}
+            Node::Import{path, alias, names, ..} => {
+                let alias = alias.map_or(String::new(), |a| format!(" as {}", pool.str_at(a)));
+                let names = match names.is_empty() {
+                    true => {String::new()}
+                    false => {format!(" ({})", inline(names).trim_start())}
+                };
+                format!("(import {}{}{})", pool.str_at(*path), alias, names)
+            }
+            Node::Public{item, ..} => {format!("(pub {})", item.emit_at(pool, depth))}
+            Node::Error{..} => {String::from("(error)")}
+            Node::_Empty => {String::from("()")}
+        }
+    }
+}
+
+fn map_items(nodes:Vec<Node>, f:&mut dyn FnMut(Node) -> Vec<Node>) -> Vec<Node> {
+    nodes.into_iter().flat_map(f).collect()
+}
+
+// the node of a single slot, like the body or callee, it's a Block if f give zero or many nodes
+//   so they're still evaluated in order and the value is of the last, like a spliced expansion
+fn map_one(node:Node, f:&mut dyn FnMut(Node) -> Vec<Node>) -> Box<Node> {
+    let span = node.span();
+    let mut nodes = f(node);
+    if nodes.len() == 1 {
+        Box::new(nodes.remove(0))
+    } else {
+        Box::new(Node::Block{span, items: nodes})
     }
 }
 
 impl Emit for Node {
     fn emit(&self, pool:&Pool) -> String {
-        format!("(Node)")
+        self.emit_at(pool, 0)
     }
 }
 
@@ -164,7 +579,25 @@ pub trait Parser {
     fn parser_pass(&mut self, lexer:&mut impl Lexer, pool:&mut Pool, diag:&mut Diag) -> Node;
 }
 
-pub fn build_v1(code:&String, pool:&mut Pool, diag:&mut Diag) -> Node {
-    let mut lex = lexer_v1::make(code);
+pub fn build_v1(code:&[u8], file:FileId, pool:&mut Pool, diag:&mut Diag) -> Node {
+    let mut lex = lexer_v1::make(code, file);
     parser_v1::pass(&mut lex, pool, diag)
 }
+
+// expand the macros defined in tree, it's after build_v1 and before compile
+pub fn expand_v1(tree:Node, pool:&mut Pool, diag:&mut Diag) -> Node {
+    macro_v1::pass(tree, pool, diag)
+}
+
+// the tree in JSON with spans and the strings of pool, the format is documented in json_v1
+pub fn json_v1(tree:&Node, pool:&Pool, source:&Source) -> String {
+    json_v1::pass(tree, pool, source)
+}
+
+pub fn make_lexer_v1(code:&[u8], file:FileId, lossless:bool) -> impl Lexer {
+    if lossless {
+        lexer_v1::make_lossless(code, file)
+    } else {
+        lexer_v1::make(code, file)
+    }
+}
@@ -0,0 +1,329 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::{HashMap,HashSet};
+use super::Node;
+use super::visit::{Visitor, Folder, walk, fold_children};
+use farvm_utils::{Diag,Pool,Span};
+use farvm_utils::pool::Id;
+
+// the expansion is stop at this depth, it's for the macro expand to itself forever
+const MAX_DEPTH:usize = 64;
+
+// The macros are MacroDefine of top level, the calls of them are replaced by the body with arguments
+//   the arguments are Node and not evaluated, they're put at the params in body
+//   it's hygienic: the names bound in body (Assign and params of inner Define) are renamed to fresh for each expansion
+//     so they can't capture or shadow the names in arguments, the fresh name is like "tmp{1}" and can't write in source
+//   and the free names of body are of the top level where macros are defined, they're marked like "helper{0}"
+//     after the item is expanded, the locals of call site with the same name are renamed to fresh and the marks are removed
+//     so a local of call site can't capture the free name of body
+//   the calls of unknown macro (like println!) are kept for compiler
+struct Object<'a> {
+    pool: &'a mut Pool,
+    diag: &'a mut Diag,
+    macros: HashMap<Id, Node>,
+    fresh: usize,
+    marked: HashMap<Id, Id>,        // the marked name to the name of top level
+}
+
+pub fn pass(tree:Node, pool:&mut Pool, diag:&mut Diag) -> Node {
+    let mut me = Object { pool, diag, macros: HashMap::new(), fresh: 0, marked: HashMap::new() };
+    let Node::Module{span, items} = tree else {
+        return me.expand(tree, 0, None)
+    };
+    let mut rest = Vec::new();
+    for item in items {
+        match item {
+            Node::MacroDefine{name, ..} => {me.define(name, item)}
+            _ => {rest.push(item)}
+        }
+    }
+    let items = rest.into_iter().map(|a| {
+        let item = me.expand(a, 0, None);
+        me.unmark(item)
+    }).collect();
+    Node::Module{span, items}
+}
+
+impl<'a> Object<'a> {
+    fn name_of(&self, name:Id) -> String {
+        self.pool.str_at(name) + "!"
+    }
+
+    fn define(&mut self, name:Id, node:Node) {
+        if let Some(before) = self.macros.get(&name) {
+            let describe = format!("macro '{}' is defined again", self.name_of(name));
+            let before = before.span();
+            self.diag.push_error(node.span(), &describe).note(before, "the first define is here");
+            return
+        }
+        self.macros.insert(name, node);
+    }
+
+    // the origin is the call in source of the outermost expansion, the inner calls are from body of macros
+    fn expand(&mut self, node:Node, depth:usize, origin:Option<Span>) -> Node {
+        match node {
+            Node::MacroCall{span, name, args} if self.macros.contains_key(&name) => {
+                let define = self.macros[&name].clone();
+                let origin = origin.unwrap_or(span);
+                if depth >= MAX_DEPTH {
+                    let describe = format!("macro '{}' is expanded too deep, more than {}", self.name_of(name), MAX_DEPTH);
+                    self.diag.push_error(origin, &describe).note(define.span(), "the macro is defined here");
+                    return Node::Error{span}
+                }
+                match self.instantiate(&define, span, origin, args) {
+                    Some(body) => {self.expand(body, depth + 1, Some(origin))}
+                    None => {Node::Error{span}}
+                }
+            }
+            Node::MacroDefine{span, name, ..} => {
+                let describe = format!("macro '{}' should be defined at top level", self.name_of(name));
+                self.diag.push_error(span, &describe);
+                Node::Error{span}
+            }
+            _ => {node.map_children(&mut |a| vec![self.expand(a, depth, origin)])}
+        }
+    }
+
+    // the item of top level without marked names, the locals are renamed if they'd capture the marked
+    fn unmark(&mut self, item:Node) -> Node {
+        if self.marked.is_empty() {
+            return item
+        }
+        let names = self.marked.values().copied().collect();
+        let mut me = Unmark { pool: self.pool, marked: &self.marked, names, scopes: Vec::new(), fresh: &mut self.fresh };
+        let item = me.fold(item);
+        self.marked.clear();
+        item
+    }
+
+    // the body of define with args at params, and the names bound in body are fresh
+    fn instantiate(&mut self, define:&Node, call:Span, origin:Span, args:Vec<Node>) -> Option<Node> {
+        let Node::MacroDefine{span, name, params, variadic, body} = define else {
+            return None
+        };
+        let names:Vec<Id> = params.iter().filter_map(|a| match a {
+            Node::Param{name, ..} => {Some(*name)}
+            _ => {None}
+        }).collect();
+        let fixed = if *variadic {names.len().saturating_sub(1)} else {names.len()};
+        if args.len() < fixed || (!*variadic && args.len() > fixed) {
+            let describe = format!("macro '{}' expects {}{} argument(s) but found {}",
+                self.name_of(*name), if *variadic {"at least "} else {""}, fixed, args.len());
+            let unit = self.diag.push_error(call, &describe).note(*span, "the macro is defined here");
+            if origin != call {
+                unit.note(origin, "it's expanded from here");
+            }
+            return None
+        }
+        let mut bound = Bound(HashSet::new());
+        bound.visit(body);
+        let mut bound = bound.0;
+        for a in &names {
+            bound.remove(a);
+        }
+        let mut free = Free(HashSet::new());
+        free.visit(body);
+        let free:Vec<Id> = free.0.into_iter().filter(|a| !bound.contains(a) && !names.contains(a)).collect();
+        self.fresh += 1;
+        let mut renames:HashMap<Id, Id> = bound.into_iter().map(|a| {
+            let fresh = format!("{}{{{}}}", self.pool.str_at(a), self.fresh);
+            (a, self.pool.add_str(fresh))
+        }).collect();
+        for a in free {
+            let marked = self.pool.add_str(format!("{}{{0}}", self.pool.str_at(a)));
+            self.marked.insert(marked, a);
+            renames.insert(a, marked);
+        }
+        let body = Rename(&renames).fold(body.as_ref().clone());
+
+        let mut args = args.into_iter();
+        let mut values:HashMap<Id, Vec<Node>> = HashMap::new();
+        for (i, a) in names.iter().enumerate() {
+            if *variadic && i == fixed {
+                values.insert(*a, args.by_ref().collect());
+            } else {
+                values.insert(*a, args.next().into_iter().collect());
+            }
+        }
+        let mut me = Substitute { values, call: origin, diag: self.diag, pool: self.pool };
+        Some(me.one(body))
+    }
+}
+
+// the params in body are replaced by arguments, the rest arguments are spliced in items like (f rest) or [rest]
+struct Substitute<'b> {
+    values: HashMap<Id, Vec<Node>>,
+    call: Span,
+    diag: &'b mut Diag,
+    pool: &'b Pool,
+}
+
+impl<'b> Substitute<'b> {
+    fn many(&mut self, node:Node) -> Vec<Node> {
+        match node {
+            Node::Ident{name, ..} if self.values.contains_key(&name) => {self.values[&name].clone()}
+            _ => {vec![self.one(node)]}
+        }
+    }
+
+    fn one(&mut self, node:Node) -> Node {
+        match node {
+            Node::Ident{span, name} if self.values.contains_key(&name) => {
+                let mut value = self.values[&name].clone();
+                if value.len() != 1 {
+                    let describe = format!("the rest parameter '{}' of macro should be in items, like (f {}) or [{}]",
+                        self.pool.str_at(name), self.pool.str_at(name), self.pool.str_at(name));
+                    self.diag.push_error(span, &describe).note(self.call, "the macro is called here");
+                    return Node::Error{span}
+                }
+                value.remove(0)
+            }
+            _ => {node.map_children(&mut |a| self.many(a))}
+        }
+    }
+}
+
+// the names bound by body, it's the Assign and the params of inner Define
+struct Bound(HashSet<Id>);
+
+impl Visitor<'_> for Bound {
+    fn visit_assign(&mut self, node:&Node) {
+        if let Node::Assign{name, ..} = node {
+            self.0.insert(*name);
+        }
+        walk(self, node)
+    }
+    fn visit_param(&mut self, node:&Node) {
+        if let Node::Param{name, ..} = node {
+            self.0.insert(*name);
+        }
+        walk(self, node)
+    }
+}
+
+// the names used by body, the free are these not bound and not params
+struct Free(HashSet<Id>);
+
+impl Visitor<'_> for Free {
+    fn visit_ident(&mut self, node:&Node) {
+        if let Node::Ident{name, ..} = node {
+            self.0.insert(*name);
+        }
+    }
+}
+
+// the bound names to the fresh names, and the free names to the marked
+struct Rename<'c>(&'c HashMap<Id, Id>);
+
+impl<'c> Rename<'c> {
+    fn to(&self, name:Id) -> Id {
+        *self.0.get(&name).unwrap_or(&name)
+    }
+}
+
+impl<'c> Folder for Rename<'c> {
+    fn fold_ident(&mut self, node:Node) -> Node {
+        match node {
+            Node::Ident{span, name} => {Node::Ident{span, name: self.to(name)}}
+            _ => {node}
+        }
+    }
+    fn fold_assign(&mut self, node:Node) -> Node {
+        match node {
+            Node::Assign{span, name, ty, value} => {
+                let ty = ty.map(|a| Box::new(self.fold(*a)));
+                let value = Box::new(self.fold(*value));
+                Node::Assign{span, name: self.to(name), ty, value}
+            }
+            _ => {node}
+        }
+    }
+    fn fold_param(&mut self, node:Node) -> Node {
+        match fold_children(self, node) {
+            Node::Param{span, name, ty} => {Node::Param{span, name: self.to(name), ty}}
+            node => {node}
+        }
+    }
+}
+
+// the marked names are back to the names of top level, and the locals would capture them are renamed to fresh
+//   the scopes are of Define, Block and the Assign after it in block, the items of top level aren't locals
+struct Unmark<'d> {
+    pool: &'d mut Pool,
+    marked: &'d HashMap<Id, Id>,
+    names: HashSet<Id>,             // the names of top level are marked
+    scopes: Vec<HashMap<Id, Id>>,   // the locals renamed
+    fresh: &'d mut usize,
+}
+
+impl<'d> Unmark<'d> {
+    // the local is renamed if it's a name marked, or it's kept
+    fn bind(&mut self, name:Id) -> Id {
+        let Some(scope) = self.scopes.last_mut() else {
+            return name
+        };
+        if !self.names.contains(&name) {
+            return name
+        }
+        *self.fresh += 1;
+        let fresh = self.pool.add_str(format!("{}{{{}}}", self.pool.str_at(name), self.fresh));
+        scope.insert(name, fresh);
+        fresh
+    }
+    fn scoped(&mut self, node:Node) -> Node {
+        self.scopes.push(HashMap::new());
+        let node = fold_children(self, node);
+        self.scopes.pop();
+        node
+    }
+}
+
+impl<'d> Folder for Unmark<'d> {
+    fn fold_ident(&mut self, node:Node) -> Node {
+        match node {
+            Node::Ident{span, name} => {
+                let name = match self.marked.get(&name) {
+                    Some(a) => {*a}
+                    None => {self.scopes.iter().rev().find_map(|a| a.get(&name).copied()).unwrap_or(name)}
+                };
+                Node::Ident{span, name}
+            }
+            _ => {node}
+        }
+    }
+    fn fold_define(&mut self, node:Node) -> Node {self.scoped(node)}
+    fn fold_block(&mut self, node:Node) -> Node {self.scoped(node)}
+    fn fold_param(&mut self, node:Node) -> Node {
+        match fold_children(self, node) {
+            Node::Param{span, name, ty} => {Node::Param{span, name: self.bind(name), ty}}
+            node => {node}
+        }
+    }
+    // the value is before the name is bound, so the name in value is of outer
+    fn fold_assign(&mut self, node:Node) -> Node {
+        match fold_children(self, node) {
+            Node::Assign{span, name, ty, value} => {Node::Assign{span, name: self.bind(name), ty, value}}
+            node => {node}
+        }
+    }
+}
@@ -20,17 +20,638 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use super::{TokenValue,Node,Lexer};
-use farvm_utils::{Diag,Pool,Emit};
+use super::{Token,TokenValue,FormatPiece,Node,Literal,Lexer,TokenStream,token_stream};
+use farvm_utils::{Diag,Pool,Span};
+use farvm_utils::pool::Id;
 
+// the errors are reported to Diag and the bad parts are Node::Error, so all errors are found in one pass
+//   the parser resync at closing delimiters of opened, and at the next define of top level
+//   a define at the column 1 of line is top level, it's end all opened groups, so one unclosed '(' don't swallow the rest
 pub fn pass(lexer:&mut impl Lexer, pool:&mut Pool, diag:&mut Diag) -> Node {
-    fn in_toplevel() {}
+    let mut ts = token_stream::make(lexer, pool, diag);
+    let start = ts.peek(0).span;
+    let mut items = Vec::new();
+    while !at_group_end(&mut ts) {
+        let errors = ts.diag().errors().len();
+        items.push(parse_item(&mut ts));
+        if ts.diag().errors().len() > errors {
+            if let Some(skipped) = sync_top_level(&mut ts) {
+                items.push(skipped);
+            }
+        }
+    }
+    Node::Module{span: start.to(ts.peek(0).span), items}
+}
+
+fn error_at(ts:&mut TokenStream<impl Lexer>, span:Span, describe:&str) {
+    ts.diag().push_error(span, describe);
+}
+
+// the span from start to the last bumped token
+fn span_from(ts:&mut TokenStream<impl Lexer>, start:Span) -> Span {
+    start.to(ts.prev_span())
+}
+
+fn is_close(val:&TokenValue) -> bool {
+    matches!(val, TokenValue::RP0 | TokenValue::RP1 | TokenValue::RP2)
+}
+
+// bump the opening delimiter, and wait the close
+fn open_group(ts:&mut TokenStream<impl Lexer>, close:TokenValue) -> Span {
+    let open = ts.bump();
+    let span = open.span;
+    ts.push_open(close, open);
+    span
+}
+
+// the group is end at a closing delimiter of opened, a define head at line start or _EOF
+//   the unmatched closing are reported and skipped
+fn at_group_end(ts:&mut TokenStream<impl Lexer>) -> bool {
+    loop {
+        let token = ts.peek(0).clone();
+        if !is_close(&token.val) {
+            return token.val == TokenValue::_EOF || (ts.has_open() && is_define_head(ts))
+        }
+        if ts.is_open(&token.val) {
+            return true
+        }
+        ts.bump();
+        error_at(ts, token.span, &format!("unmatched {}", token.val.kind_name()));
+    }
+}
+
+// bump the close of last opened group, or report it's unclosed and point to the opening
+//   the token isn't bump if it's not the close, it's close some outer group or it's _EOF
+fn close_group(ts:&mut TokenStream<impl Lexer>) {
+    let Some((close, open)) = ts.pop_open() else {
+        return
+    };
+    if ts.eat(&close).is_some() {
+        return
+    }
+    let found = ts.peek(0).clone();
+    let describe = format!("expect {} but found {}", close.kind_name(), found.val.kind_name());
+    let note = format!("unclosed {} is opened here", open.val.kind_name());
+    ts.diag().push_error(found.span, &describe).note(open.span, &note);
+}
+
+// skip the rest of group after an error, the nested groups are skipped whole
+fn skip_to_group_end(ts:&mut TokenStream<impl Lexer>) {
+    let mut depth = 0;
     loop {
-        let token = lexer.lexer_pass(pool, diag);
-        if token.val == TokenValue::_EOF {
-            break
+        if depth == 0 && at_group_end(ts) {
+            return
+        }
+        if is_define_head(ts) {
+            return
+        }
+        match ts.peek(0).val {
+            TokenValue::LP0 | TokenValue::LP1 | TokenValue::LP2 => {depth += 1}
+            TokenValue::RP0 | TokenValue::RP1 | TokenValue::RP2 => {depth -= 1}
+            TokenValue::_EOF => {return}
+            _ => {}
+        }
+        ts.bump();
+    }
+}
+
+// skip to the next define of top level after an error, the skipped tokens are a Node::Error
+//   it's stop at the closing delimiters, they're unmatched in top level and reported by at_group_end
+fn sync_top_level(ts:&mut TokenStream<impl Lexer>) -> Option<Node> {
+    let start = ts.peek(0).span;
+    let mut skipped = false;
+    loop {
+        match ts.peek(0).val.clone() {
+            TokenValue::_EOF | TokenValue::KeyFor => {break}
+            TokenValue::RP0 | TokenValue::RP1 | TokenValue::RP2 => {break}
+            TokenValue::LP0 if is_define_ahead(ts) => {break}
+            TokenValue::Id(_) if ts.peek_is(1, &TokenValue::EQUAL) => {break}
+            _ => {ts.bump(); skipped = true}
+        }
+    }
+    skipped.then(|| Node::Error{span: span_from(ts, start)})
+}
+
+// Item = (or ModuleDecl Import Public Statement), the items of top level
+fn parse_item(ts:&mut TokenStream<impl Lexer>) -> Node {
+    match ts.peek(0).val {
+        TokenValue::KeyModule => {
+            let start = ts.bump().span;
+            let path = parse_path(ts);
+            Node::ModuleDecl{span: span_from(ts, start), path}
+        }
+        TokenValue::KeyImport => {parse_import(ts)}
+        TokenValue::KeyPub => {
+            let start = ts.bump().span;
+            let item = parse_statement(ts);
+            match item {
+                Node::Define{..} | Node::DispatchGroup{..} | Node::Assign{..} | Node::Error{..} => {}
+                _ => {error_at(ts, item.span(), "expect define, dispatch or assign after 'pub'")}
+            }
+            Node::Public{span: span_from(ts, start), item: Box::new(item)}
+        }
+        _ => {parse_statement(ts)}
+    }
+}
+
+// Path = (order Id (any (order '.' Id))), it's the name of module like "geo.shapes"
+fn parse_path(ts:&mut TokenStream<impl Lexer>) -> Id {
+    let first = expect_name(ts, "name of module");
+    let mut path = ts.pool().str_at(first);
+    while ts.eat(&TokenValue::DOT).is_some() {
+        let name = expect_name(ts, "name of module after '.'");
+        path = path + "." + &ts.pool().str_at(name);
+    }
+    ts.pool().add_str(path)
+}
+
+// Import = (order 'import' Path (opt (order 'as' Id)) (opt (order '{' (any Id) '}')))
+//   the module is named by alias or the last of path, the names in '{}' are imported without the module name
+fn parse_import(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = ts.bump().span;
+    let path = parse_path(ts);
+    let alias = ts.eat(&TokenValue::KeyAs).map(|_| expect_name(ts, "alias of module"));
+    let mut names = Vec::new();
+    if ts.peek_is(0, &TokenValue::LP2) {
+        open_group(ts, TokenValue::RP2);
+        while !at_group_end(ts) {
+            let token = ts.bump();
+            match token.val {
+                TokenValue::Id(name) => {names.push(Node::Ident{span: token.span, name})}
+                _ => {error_at(ts, token.span, &format!("expect name to import but found {}", token.val.kind_name()))}
+            }
+        }
+        close_group(ts);
+    }
+    Node::Import{span: span_from(ts, start), path, alias, names}
+}
+
+// Statement = (or DispatchGroup MacroDefine Define Assign Rebind Expr)
+fn parse_statement(ts:&mut TokenStream<impl Lexer>) -> Node {
+    match ts.peek(0).val.clone() {
+        TokenValue::KeyFor => {parse_dispatch_group(ts)}
+        TokenValue::LP0 if is_define_ahead(ts) => {
+            match ts.peek(1).val {
+                TokenValue::MacroId(_) => {parse_macro_define(ts)}
+                _ => {parse_define(ts)}
+            }
+        }
+        TokenValue::Id(_) if ts.peek_is(1, &TokenValue::EQUAL) || ts.peek_is(1, &TokenValue::COLON) => {parse_assign(ts)}
+        _ => {
+            let expr = parse_expr(ts);
+            if ts.eat(&TokenValue::ASSIGN).is_some() {
+                let value = parse_expr(ts);
+                let span = expr.span().to(value.span());
+                Node::Rebind{span, target: Box::new(expr), value: Box::new(value)}
+            } else {
+                expr
+            }
+        }
+    }
+}
+
+// like "(name params) =" at the column 1 of line, it's a define of top level
+fn is_define_head(ts:&mut TokenStream<impl Lexer>) -> bool {
+    let token = ts.peek(0);
+    token.line_start && token.val == TokenValue::LP0 && is_define_ahead(ts)
+}
+
+// the "(...) =" is head of Define, or it's a Call
+fn is_define_ahead(ts:&mut TokenStream<impl Lexer>) -> bool {
+    let mut depth = 0;
+    let mut n = 0;
+    loop {
+        match ts.peek(n).val {
+            TokenValue::LP0 => {depth += 1}
+            TokenValue::RP0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return ts.peek_is(n + 1, &TokenValue::EQUAL)
+                }
+            }
+            TokenValue::_EOF => {return false}
+            _ => {}
+        }
+        n += 1;
+    }
+}
+
+// Define = (order '(' Id (opt Generics) (any Param) ')' '=' Expr)
+fn parse_define(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = open_group(ts, TokenValue::RP0);
+    let name = expect_name(ts, "name of define");
+    let generics = parse_generics(ts);
+    let mut params = Vec::new();
+    while !at_group_end(ts) {
+        let param = parse_param(ts);
+        let is_error = matches!(param, Node::Error{..});
+        params.push(param);
+        if is_error {
+            skip_to_group_end(ts);
+        }
+    }
+    close_group(ts);
+    ts.expect(&TokenValue::EQUAL);
+    let body = parse_expr(ts);
+    Node::Define{span: span_from(ts, start), name, generics, params, body: Box::new(body)}
+}
+
+// MacroDefine = (order '(' MacroId (any Id) (opt '...') ')' '=' Expr), the params are untyped
+//   the '...' is make the last param take the rest arguments
+fn parse_macro_define(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = open_group(ts, TokenValue::RP0);
+    let TokenValue::MacroId(name) = ts.bump().val else {
+        unreachable!("parse_macro_define is after the MacroId is peeked")
+    };
+    let mut params = Vec::new();
+    let mut variadic = false;
+    while !at_group_end(ts) {
+        if let Some(token) = ts.eat(&TokenValue::ELLIPSIS) {
+            if params.is_empty() || variadic {
+                error_at(ts, token.span, "expect '...' after the last parameter of macro");
+            }
+            variadic = true;
+            continue
+        }
+        let param = parse_param(ts);
+        match &param {
+            Node::Param{span, ty: Some(_), ..} => {error_at(ts, *span, "parameter of macro can't have a type")}
+            Node::Param{span, ..} if variadic => {error_at(ts, *span, "expect no parameter after '...' of macro")}
+            _ => {}
+        }
+        let is_error = matches!(param, Node::Error{..});
+        params.push(param);
+        if is_error {
+            skip_to_group_end(ts);
+        }
+    }
+    close_group(ts);
+    ts.expect(&TokenValue::EQUAL);
+    let body = parse_expr(ts);
+    Node::MacroDefine{span: span_from(ts, start), name, params, variadic, body: Box::new(body)}
+}
+
+// Generics = (order '<' (any Id) '>'), the '<' is after name without spaces, like (map<T U> ...)
+fn parse_generics(ts:&mut TokenStream<impl Lexer>) -> Vec<Node> {
+    let mut generics = Vec::new();
+    if !(ts.peek_is(0, &TokenValue::LP3) && ts.peek(0).span.start == ts.prev_span().end) {
+        return generics
+    }
+    let open = ts.bump();
+    while let TokenValue::Id(name) = ts.peek(0).val {
+        let span = ts.bump().span;
+        generics.push(Node::TypeParam{span, name});
+    }
+    close_angle(ts, &open);
+    generics
+}
+
+// the '>' of generics, or report it's unclosed and point to the '<'
+fn close_angle(ts:&mut TokenStream<impl Lexer>, open:&Token) {
+    if ts.eat(&TokenValue::RP3).is_some() {
+        return
+    }
+    let found = ts.peek(0).clone();
+    let describe = format!("expect '>' but found {}", found.val.kind_name());
+    ts.diag().push_error(found.span, &describe).note(open.span, "unclosed '<' is opened here");
+}
+
+// DispatchGroup = (order 'for' Id '(' Id (opt '...') ')' '{' (any Method) '}')
+fn parse_dispatch_group(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = ts.bump().span;
+    let strategy = expect_name(ts, "strategy of dispatch");
+    if !ts.peek_is(0, &TokenValue::LP0) {
+        ts.expect(&TokenValue::LP0);
+        return Node::Error{span: span_from(ts, start)}
+    }
+    open_group(ts, TokenValue::RP0);
+    let name = expect_name(ts, "name of dispatch");
+    let variadic = ts.eat(&TokenValue::ELLIPSIS).is_some();
+    close_group(ts);
+    if !ts.peek_is(0, &TokenValue::LP2) {
+        ts.expect(&TokenValue::LP2);
+        return Node::Error{span: span_from(ts, start)}
+    }
+    open_group(ts, TokenValue::RP2);
+    let mut methods = Vec::new();
+    while !at_group_end(ts) {
+        methods.push(parse_method(ts, name));
+    }
+    close_group(ts);
+    Node::DispatchGroup{span: span_from(ts, start), strategy, name, variadic, methods}
+}
+
+// Method = (order '(' (any Param) ')' '=' Expr), it's a Define named by the group, all Param need a Type
+fn parse_method(ts:&mut TokenStream<impl Lexer>, name:Id) -> Node {
+    let start = ts.peek(0).span;
+    if !ts.peek_is(0, &TokenValue::LP0) {
+        ts.expect(&TokenValue::LP0);
+        ts.bump();
+        return Node::Error{span: start}
+    }
+    open_group(ts, TokenValue::RP0);
+    let mut params = Vec::new();
+    while !at_group_end(ts) {
+        let param = parse_param(ts);
+        if let Node::Param{span, name: param_name, ty: None} = param {
+            let describe = format!("parameter '{}' of method '{}' needs a type annotation",
+                ts.pool().str_at(param_name), ts.pool().str_at(name));
+            error_at(ts, span, &describe);
+        }
+        let is_error = matches!(param, Node::Error{..});
+        params.push(param);
+        if is_error {
+            skip_to_group_end(ts);
+        }
+    }
+    close_group(ts);
+    ts.expect(&TokenValue::EQUAL);
+    let body = parse_expr(ts);
+    Node::Define{span: span_from(ts, start), name, generics: Vec::new(), params, body: Box::new(body)}
+}
+
+fn expect_name(ts:&mut TokenStream<impl Lexer>, what:&str) -> Id {
+    match ts.peek(0).val {
+        TokenValue::Id(a) => {ts.bump(); a}
+        _ => {
+            let token = ts.peek(0).clone();
+            error_at(ts, token.span, &format!("expect {} but found {}", what, token.val.kind_name()));
+            ts.pool().add_str("")
+        }
+    }
+}
+
+// Param = (order Id (opt (order ':' Type)))
+fn parse_param(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let token = ts.bump();
+    let TokenValue::Id(name) = token.val else {
+        error_at(ts, token.span, &format!("expect parameter but found {}", token.val.kind_name()));
+        return Node::Error{span: token.span}
+    };
+    let ty = if ts.eat(&TokenValue::COLON).is_some() {
+        Some(Box::new(parse_type(ts)))
+    } else {
+        None
+    };
+    Node::Param{span: span_from(ts, token.span), name, ty}
+}
+
+// Type = (or 'auto' (order Id (opt (order '<' (any Type) '>'))) FunctionType)
+//   FunctionType = (order '(' (any Type) '->' Type ')')
+fn parse_type(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let token = ts.peek(0).clone();
+    match token.val {
+        TokenValue::KeyAuto => {
+            ts.bump();
+            Node::AutoType{span: token.span}
+        }
+        TokenValue::Id(name) => {
+            ts.bump();
+            let base = Node::TypeRef{span: token.span, name};
+            if !ts.peek_is(0, &TokenValue::LP3) {
+                return base
+            }
+            let open = ts.bump();
+            let mut args = Vec::new();
+            while !(ts.peek_is(0, &TokenValue::RP3) || at_group_end(ts)) {
+                args.push(parse_type(ts));
+            }
+            close_angle(ts, &open);
+            Node::Generic{span: span_from(ts, token.span), base: Box::new(base), args}
+        }
+        TokenValue::LP0 => {
+            let start = open_group(ts, TokenValue::RP0);
+            let mut params = Vec::new();
+            while !(ts.peek_is(0, &TokenValue::ARROW) || at_group_end(ts)) {
+                params.push(parse_type(ts));
+            }
+            let ret = match ts.expect(&TokenValue::ARROW) {
+                Some(_) => {parse_type(ts)}
+                None => {Node::Error{span: ts.peek(0).span}}
+            };
+            close_group(ts);
+            Node::FunctionType{span: span_from(ts, start), params, ret: Box::new(ret)}
+        }
+        _ => {
+            error_at(ts, token.span, &format!("expect type but found {}", token.val.kind_name()));
+            Node::Error{span: Span { end: token.span.start, ..token.span }}
+        }
+    }
+}
+
+// Assign = (order Id (opt (order ':' Type)) '=' Expr)
+fn parse_assign(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let token = ts.bump();
+    let TokenValue::Id(name) = token.val else {
+        return Node::Error{span: token.span}
+    };
+    let ty = if ts.eat(&TokenValue::COLON).is_some() {
+        Some(Box::new(parse_type(ts)))
+    } else {
+        None
+    };
+    if ts.expect(&TokenValue::EQUAL).is_none() {
+        return Node::Error{span: span_from(ts, token.span)}
+    }
+    let value = parse_expr(ts);
+    Node::Assign{span: span_from(ts, token.span), name, ty, value: Box::new(value)}
+}
+
+// Expr = (order Primary (any (order '.' Id)))
+fn parse_expr(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let mut expr = parse_primary(ts);
+    while ts.eat(&TokenValue::DOT).is_some() {
+        let token = ts.peek(0).clone();
+        match token.val {
+            TokenValue::Id(member) => {
+                ts.bump();
+                let span = expr.span().to(token.span);
+                expr = Node::MemberAccess{span, object: Box::new(expr), member};
+            }
+            _ => {
+                error_at(ts, token.span, &format!("expect member name after '.' but found {}", token.val.kind_name()));
+                return Node::Error{span: span_from(ts, expr.span())}
+            }
+        }
+    }
+    expr
+}
+
+// Primary = (or Call MacroCall List Block Object Literal Format Id MacroId)
+//   the MacroId isn't called is a value, like "println!" in (pipe md println!), it's Id with the '!'
+fn parse_primary(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let token = ts.peek(0).clone();
+    let literal = |value:Literal| Node::Literal{span: token.span, value};
+    match token.val {
+        TokenValue::LP0 => {parse_call(ts)}
+        TokenValue::LP1 => {parse_list(ts)}
+        TokenValue::LP2 => {parse_block(ts)}
+        TokenValue::AT => {parse_object(ts)}
+        TokenValue::Id(name) => {ts.bump(); Node::Ident{span: token.span, name}}
+        TokenValue::MacroId(a) => {
+            ts.bump();
+            let name = ts.pool().str_at(a) + "!";
+            Node::Ident{span: token.span, name: ts.pool().add_str(name)}
+        }
+        TokenValue::LiteralInteger(a, suffix) => {ts.bump(); literal(Literal::Integer(a, suffix))}
+        TokenValue::LiteralFloat(a, suffix) => {ts.bump(); literal(Literal::Float(a, suffix))}
+        TokenValue::LiteralString(ref a) => {ts.bump(); literal(Literal::String(a.clone()))}
+        TokenValue::LiteralChar(a) => {ts.bump(); literal(Literal::Char(a))}
+        TokenValue::KeyTrue => {ts.bump(); literal(Literal::Bool(true))}
+        TokenValue::KeyFalse => {ts.bump(); literal(Literal::Bool(false))}
+        TokenValue::KeyNil => {ts.bump(); literal(Literal::Nil)}
+        TokenValue::LiteralFormat(ref pieces) => {ts.bump(); parse_format(ts, pieces, token.span)}
+        TokenValue::LP3 | TokenValue::RP3 => {parse_comparison(ts)}
+        // the closing delimiter of some group, keep it for the group
+        _ if is_close(&token.val) || token.val == TokenValue::_EOF => {
+            error_at(ts, token.span, &format!("expect expression but found {}", token.val.kind_name()));
+            Node::Error{span: Span { end: token.span.start, ..token.span }}
+        }
+        _ => {
+            ts.bump();
+            error_at(ts, token.span, &format!("unexpected {}", token.val.kind_name()));
+            Node::Error{span: token.span}
+        }
+    }
+}
+
+// Object = (order '@' (opt Parent) '{' (any Slot) '}')
+//   Parent = (order Id (any (order '.' Id))), the object is made with the parent as prototype
+fn parse_object(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = ts.bump().span;
+    let parent = match ts.peek(0).val {
+        TokenValue::Id(_) => {Some(Box::new(parse_expr(ts)))}
+        _ => {None}
+    };
+    if !ts.peek_is(0, &TokenValue::LP2) {
+        ts.expect(&TokenValue::LP2);
+        return Node::Error{span: span_from(ts, start)}
+    }
+    open_group(ts, TokenValue::RP2);
+    let mut slots = Vec::new();
+    while !at_group_end(ts) {
+        let slot = parse_slot(ts);
+        let is_error = matches!(slot, Node::Error{..});
+        slots.push(slot);
+        if is_error {
+            skip_to_group_end(ts);
+        }
+    }
+    close_group(ts);
+    Node::ObjectLiteral{span: span_from(ts, start), parent, slots}
+}
+
+// Slot = (order Id ':' Expr)
+fn parse_slot(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let token = ts.peek(0).clone();
+    let TokenValue::Id(name) = token.val else {
+        error_at(ts, token.span, &format!("expect slot name but found {}", token.val.kind_name()));
+        return Node::Error{span: token.span}
+    };
+    ts.bump();
+    if ts.expect(&TokenValue::COLON).is_none() {
+        return Node::Error{span: token.span}
+    }
+    let value = parse_expr(ts);
+    Node::Slot{span: span_from(ts, token.span), name, value: Box::new(value)}
+}
+
+// Comparison = (or '<' '>' "<=" ">="), they're Id in expression like (< a b), the "<-" is lexed to ASSIGN
+fn parse_comparison(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let token = ts.bump();
+    let mut text = String::from(if token.val == TokenValue::LP3 {"<"} else {">"});
+    let mut span = token.span;
+    if ts.peek_is(0, &TokenValue::EQUAL) && ts.peek(0).span.start == span.end {
+        span = span.to(ts.bump().span);
+        text.push('=');
+    }
+    Node::Ident{span, name: ts.pool().add_str(text)}
+}
+
+// Call = (order '(' Expr (any Expr) ')')
+// MacroCall = (order '(' MacroId (any Expr) ')')
+fn parse_call(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = open_group(ts, TokenValue::RP0);
+    if ts.peek_is(0, &TokenValue::RP0) {
+        close_group(ts);
+        let span = span_from(ts, start);
+        error_at(ts, span, "empty call '()'");
+        return Node::Error{span}
+    }
+    let macro_name = match ts.peek(0).val {
+        TokenValue::MacroId(a) => {ts.bump(); Some(a)}
+        _ => {None}
+    };
+    let callee = match macro_name {
+        Some(_) => {Node::_Empty}
+        None => {parse_expr(ts)}
+    };
+    let args = parse_exprs_until_close(ts);
+    close_group(ts);
+    let span = span_from(ts, start);
+    match macro_name {
+        Some(name) => {Node::MacroCall{span, name, args}}
+        None => {Node::Call{span, callee: Box::new(callee), args}}
+    }
+}
+
+fn parse_exprs_until_close(ts:&mut TokenStream<impl Lexer>) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    while !at_group_end(ts) {
+        nodes.push(parse_expr(ts));
+    }
+    nodes
+}
+
+// List = (order '[' (any Expr) ']')
+fn parse_list(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = open_group(ts, TokenValue::RP1);
+    let items = parse_exprs_until_close(ts);
+    close_group(ts);
+    Node::List{span: span_from(ts, start), items}
+}
+
+// Block = (order '{' (any Statement) '}')
+//   the Define and DispatchGroup are globals, so they're reported in block
+//   and become a local of Node::Error, the uses of the name after aren't reported again
+fn parse_block(ts:&mut TokenStream<impl Lexer>) -> Node {
+    let start = open_group(ts, TokenValue::RP2);
+    let mut items = Vec::new();
+    while !at_group_end(ts) {
+        let item = parse_statement(ts);
+        match item {
+            Node::Define{span, name, ..} | Node::DispatchGroup{span, name, ..} => {
+                let describe = format!("'{}' should be defined at top level", ts.pool().str_at(name));
+                error_at(ts, span, &describe);
+                items.push(Node::Assign{span, name, ty: None, value: Box::new(Node::Error{span})});
+            }
+            _ => {items.push(item)}
+        }
+    }
+    close_group(ts);
+    Node::Block{span: span_from(ts, start), items}
+}
+
+// Format = (any (or Text (order '{' Expr '}'))), the tokens of Expr are lexed in LiteralFormat
+fn parse_format(ts:&mut TokenStream<impl Lexer>, pieces:&[FormatPiece], span:Span) -> Node {
+    let mut parts = Vec::new();
+    for piece in pieces {
+        match piece {
+            FormatPiece::Text(a) => {
+                parts.push(Node::Literal{span, value: Literal::String(a.clone())});
+            }
+            FormatPiece::Expr(tokens) => {
+                let Some(last) = tokens.last() else {continue};
+                let end = Span { start: last.span.end, ..last.span };
+                let mut replay = token_stream::replay(tokens.clone(), end);
+                let mut sub = ts.nested(&mut replay);
+                parts.push(parse_expr(&mut sub));
+                if !sub.at_eof() {
+                    let extra = sub.peek(0).span;
+                    error_at(&mut sub, extra, "expect only one expression in '{}' of string");
+                }
+            }
         }
-        println!("{}", token.emit(pool))
     }
-    Node::_Empty
+    Node::Format{span, parts}
 }
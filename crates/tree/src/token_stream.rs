@@ -0,0 +1,158 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::{TokenValue,Token,Lexer};
+use farvm_utils::{Diag, Pool, Span};
+
+// The buffered tokens from any Lexer, for parsers to look ahead and go back
+//   all tokens are kept after lexed, so rewind to a Checkpoint never lex again
+pub struct Object<'a, L:Lexer> {
+    lexer: &'a mut L,
+    pool: &'a mut Pool,
+    diag: &'a mut Diag,
+    tokens: Vec<Token>,
+    i: usize,
+    opens: Vec<(TokenValue, Token)>,
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Checkpoint(usize);
+
+pub fn make<'a, L:Lexer>(lexer:&'a mut L, pool:&'a mut Pool, diag:&'a mut Diag) -> Object<'a, L> {
+    Object { lexer, pool, diag, tokens: Vec::new(), i: 0, opens: Vec::new() }
+}
+
+// A Lexer give the tokens lexed before, like the tokens of "{expr}" in string
+pub struct Replay {
+    tokens: std::vec::IntoIter<Token>,
+    end: Span,
+}
+
+// the end is span of _EOF after all tokens
+pub fn replay(tokens:Vec<Token>, end:Span) -> Replay {
+    Replay { tokens: tokens.into_iter(), end }
+}
+
+impl Lexer for Replay {
+    fn lexer_pass(&mut self, _pool:&mut Pool, _diag:&mut Diag) -> Token {
+        match self.tokens.next() {
+            Some(a) => {a}
+            None => {Token { span: self.end, ..Token::default() }}
+        }
+    }
+}
+
+impl<'a, L:Lexer> Object<'a, L> {
+    pub fn pool(&mut self) -> &mut Pool {self.pool}
+    pub fn diag(&mut self) -> &mut Diag {self.diag}
+
+    // the stream of other lexer, it's share the pool and diag with self
+    pub fn nested<'b, M:Lexer>(&'b mut self, lexer:&'b mut M) -> Object<'b, M> {
+        make(lexer, self.pool, self.diag)
+    }
+
+    // lex until has the i-th token, the _EOF is repeat after end
+    fn fill_to(&mut self, i:usize) {
+        while self.tokens.len() <= i {
+            if let Some(last) = self.tokens.last() {
+                if last.val == TokenValue::_EOF {
+                    let eof = last.clone();
+                    self.tokens.push(eof);
+                    continue
+                }
+            }
+            let token = self.lexer.lexer_pass(self.pool, self.diag);
+            self.tokens.push(token);
+        }
+    }
+
+    // the n-th token after now, peek(0) is the token will bump
+    pub fn peek(&mut self, n:usize) -> &Token {
+        self.fill_to(self.i + n);
+        &self.tokens[self.i + n]
+    }
+    pub fn peek_is(&mut self, n:usize, kind:&TokenValue) -> bool {
+        self.peek(n).val.is_kind(kind)
+    }
+    pub fn at_eof(&mut self) -> bool {
+        self.peek(0).val == TokenValue::_EOF
+    }
+
+    pub fn bump(&mut self) -> Token {
+        let token = self.peek(0).clone();
+        if token.val != TokenValue::_EOF {
+            self.i += 1;
+        }
+        token
+    }
+    // bump if the token is kind, or keep it
+    pub fn eat(&mut self, kind:&TokenValue) -> Option<Token> {
+        if self.peek_is(0, kind) {
+            Some(self.bump())
+        } else {
+            None
+        }
+    }
+    // like eat, but report an error at the token if it isn't kind
+    pub fn expect(&mut self, kind:&TokenValue) -> Option<Token> {
+        if let Some(token) = self.eat(kind) {
+            return Some(token)
+        }
+        let found = self.peek(0).clone();
+        let describe = format!("expect {} but found {}", kind.kind_name(), found.val.kind_name());
+        self.diag.push_error(found.span, &describe);
+        None
+    }
+
+    // the span of the last bumped token, or the span before the first token
+    pub fn prev_span(&mut self) -> Span {
+        if self.i > 0 {
+            self.tokens[self.i - 1].span
+        } else {
+            let span = self.peek(0).span;
+            Span { end: span.start, ..span }
+        }
+    }
+
+    // the delimiters opened by parser, it's the closing waited and the opening token
+    //   a closing delimiter isn't opened is unmatched, or it's close some opened and the inner are unclosed
+    pub fn push_open(&mut self, close:TokenValue, open:Token) {
+        self.opens.push((close, open))
+    }
+    pub fn pop_open(&mut self) -> Option<(TokenValue, Token)> {
+        self.opens.pop()
+    }
+    pub fn is_open(&self, close:&TokenValue) -> bool {
+        self.opens.iter().any(|(a, _)| a.is_kind(close))
+    }
+    pub fn has_open(&self) -> bool {
+        !self.opens.is_empty()
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.i)
+    }
+    // the Diag isn't rewind, so the errors in speculative parse should be pushed after it's decided
+    pub fn rewind(&mut self, to:Checkpoint) {
+        self.i = to.0
+    }
+}
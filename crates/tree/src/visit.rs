@@ -0,0 +1,164 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::Node;
+
+// The walkers of Node for the phases, a phase override the methods of nodes it cares about
+//   the default methods go on to the children, so an override should call walk or fold_children to go on
+//   the dispatch are exhaustive match, so a new variant of Node is an error here until it's walked
+
+// Visit the nodes by reference, like collect the names or check the types
+//   the 't is the borrow of tree, so a visitor can keep the nodes or their NodeId
+pub trait Visitor<'t> {
+    fn visit(&mut self, node:&'t Node) {
+        match node {
+            Node::Module{..} => {self.visit_module(node)}
+            Node::Define{..} => {self.visit_define(node)}
+            Node::Param{..} => {self.visit_param(node)}
+            Node::TypeParam{..} => {self.visit_type_param(node)}
+            Node::TypeRef{..} => {self.visit_type_ref(node)}
+            Node::AutoType{..} => {self.visit_auto_type(node)}
+            Node::Generic{..} => {self.visit_generic(node)}
+            Node::FunctionType{..} => {self.visit_function_type(node)}
+            Node::DispatchGroup{..} => {self.visit_dispatch_group(node)}
+            Node::Call{..} => {self.visit_call(node)}
+            Node::MacroCall{..} => {self.visit_macro_call(node)}
+            Node::MacroDefine{..} => {self.visit_macro_define(node)}
+            Node::Block{..} => {self.visit_block(node)}
+            Node::List{..} => {self.visit_list(node)}
+            Node::Literal{..} => {self.visit_literal(node)}
+            Node::Format{..} => {self.visit_format(node)}
+            Node::Ident{..} => {self.visit_ident(node)}
+            Node::MemberAccess{..} => {self.visit_member_access(node)}
+            Node::ObjectLiteral{..} => {self.visit_object_literal(node)}
+            Node::Slot{..} => {self.visit_slot(node)}
+            Node::Assign{..} => {self.visit_assign(node)}
+            Node::Rebind{..} => {self.visit_rebind(node)}
+            Node::ModuleDecl{..} => {self.visit_module_decl(node)}
+            Node::Import{..} => {self.visit_import(node)}
+            Node::Public{..} => {self.visit_public(node)}
+            Node::Error{..} => {self.visit_error(node)}
+            Node::_Empty => {self.visit_empty(node)}
+        }
+    }
+    fn visit_module(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_define(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_param(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_type_param(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_type_ref(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_auto_type(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_generic(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_function_type(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_dispatch_group(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_call(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_macro_call(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_macro_define(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_block(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_list(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_literal(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_format(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_ident(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_member_access(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_object_literal(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_slot(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_assign(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_rebind(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_module_decl(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_import(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_public(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_error(&mut self, node:&'t Node) {walk(self, node)}
+    fn visit_empty(&mut self, node:&'t Node) {walk(self, node)}
+}
+
+// visit the children of node
+pub fn walk<'t, V:Visitor<'t> + ?Sized>(visitor:&mut V, node:&'t Node) {
+    for a in node.children() {
+        visitor.visit(a);
+    }
+}
+
+// Fold the nodes by value to the new nodes, like rename or expand
+pub trait Folder {
+    fn fold(&mut self, node:Node) -> Node {
+        match node {
+            Node::Module{..} => {self.fold_module(node)}
+            Node::Define{..} => {self.fold_define(node)}
+            Node::Param{..} => {self.fold_param(node)}
+            Node::TypeParam{..} => {self.fold_type_param(node)}
+            Node::TypeRef{..} => {self.fold_type_ref(node)}
+            Node::AutoType{..} => {self.fold_auto_type(node)}
+            Node::Generic{..} => {self.fold_generic(node)}
+            Node::FunctionType{..} => {self.fold_function_type(node)}
+            Node::DispatchGroup{..} => {self.fold_dispatch_group(node)}
+            Node::Call{..} => {self.fold_call(node)}
+            Node::MacroCall{..} => {self.fold_macro_call(node)}
+            Node::MacroDefine{..} => {self.fold_macro_define(node)}
+            Node::Block{..} => {self.fold_block(node)}
+            Node::List{..} => {self.fold_list(node)}
+            Node::Literal{..} => {self.fold_literal(node)}
+            Node::Format{..} => {self.fold_format(node)}
+            Node::Ident{..} => {self.fold_ident(node)}
+            Node::MemberAccess{..} => {self.fold_member_access(node)}
+            Node::ObjectLiteral{..} => {self.fold_object_literal(node)}
+            Node::Slot{..} => {self.fold_slot(node)}
+            Node::Assign{..} => {self.fold_assign(node)}
+            Node::Rebind{..} => {self.fold_rebind(node)}
+            Node::ModuleDecl{..} => {self.fold_module_decl(node)}
+            Node::Import{..} => {self.fold_import(node)}
+            Node::Public{..} => {self.fold_public(node)}
+            Node::Error{..} => {self.fold_error(node)}
+            Node::_Empty => {self.fold_empty(node)}
+        }
+    }
+    fn fold_module(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_define(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_param(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_type_param(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_type_ref(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_auto_type(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_generic(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_function_type(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_dispatch_group(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_call(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_macro_call(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_macro_define(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_block(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_list(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_literal(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_format(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_ident(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_member_access(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_object_literal(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_slot(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_assign(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_rebind(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_module_decl(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_import(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_public(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_error(&mut self, node:Node) -> Node {fold_children(self, node)}
+    fn fold_empty(&mut self, node:Node) -> Node {fold_children(self, node)}
+}
+
+// fold the children of node, the node itself is kept
+pub fn fold_children<F:Folder + ?Sized>(folder:&mut F, node:Node) -> Node {
+    node.map_children(&mut |a| vec![folder.fold(a)])
+}
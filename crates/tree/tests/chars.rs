@@ -0,0 +1,66 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::TokenValue;
+
+fn char_of(code:&str) -> char {
+    let (tokens, errors) = lex(code);
+    assert!(errors.is_empty(), "{}: {:?}", code, errors);
+    match &tokens[..] {
+        [TokenValue::LiteralChar(a)] => {*a}
+        _ => {panic!("{} isn't one char: {:?}", code, tokens)}
+    }
+}
+
+#[test]
+fn plain_and_utf8() {
+    assert_eq!(char_of("'a'"), 'a');
+    assert_eq!(char_of("'é'"), 'é');
+    assert_eq!(char_of("'😀'"), '😀');
+    assert_eq!(char_of("'\"'"), '"');
+}
+
+#[test]
+fn escapes() {
+    assert_eq!(char_of("'\\n'"), '\n');
+    assert_eq!(char_of("'\\t'"), '\t');
+    assert_eq!(char_of("'\\''"), '\'');
+    assert_eq!(char_of("'\\\\'"), '\\');
+    assert_eq!(char_of("'\\u{1F600}'"), '😀');
+}
+
+#[test]
+fn bad_chars() {
+    assert_eq!(lex_errors("'ab'"), vec!["char should be one character, the string is \"...\""]);
+    assert_eq!(lex_errors("''"), vec!["char should be one character, the string is \"...\""]);
+    assert_eq!(lex_errors("'a\nx"), vec!["missing closing ' of char literal"]);
+    assert_eq!(lex_errors("'\\q'"), vec!["unknown escape '\\q' in string"]);
+}
+
+#[test]
+fn char_node() {
+    let (tree, errors) = emit("(f) = ['a' '\\n']");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(list 'a' '\\n')"), "{}", tree);
+}
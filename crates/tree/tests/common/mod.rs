@@ -0,0 +1,85 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+// The helpers of tests, every test file uses a part of them
+#![allow(dead_code)]
+
+use farvm_tree::{Lexer, Node, Token, TokenValue};
+use farvm_utils::{diag, pool, Diag, Emit, Pool};
+use farvm_utils::diag::DiagUnit;
+
+pub fn describes(units:&[DiagUnit]) -> Vec<String> {
+    units.iter().map(|a| a.describe()).collect()
+}
+
+// the tokens until _EOF, the lossless keeps the trivia in them
+pub fn tokens(code:&[u8], lossless:bool) -> (Vec<Token>, Pool, Diag) {
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let mut lexer = farvm_tree::make_lexer_v1(code, 0, lossless);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.lexer_pass(&mut unique_pool, &mut the_diag);
+        if token.val == TokenValue::_EOF {
+            break
+        }
+        tokens.push(token);
+    }
+    (tokens, unique_pool, the_diag)
+}
+
+// the values of tokens and the errors
+pub fn lex(code:&str) -> (Vec<TokenValue>, Vec<String>) {
+    let (tokens, _, the_diag) = tokens(code.as_bytes(), false);
+    (tokens.into_iter().map(|a| a.val).collect(), describes(the_diag.errors()))
+}
+
+pub fn lex_errors(code:&str) -> Vec<String> {
+    lex(code).1
+}
+
+pub fn parse(code:&str) -> (Node, Pool, Diag) {
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let tree = farvm_tree::build_v1(code.as_bytes(), 0, &mut unique_pool, &mut the_diag);
+    (tree, unique_pool, the_diag)
+}
+
+// the tree in S-expression and the errors
+pub fn emit(code:&str) -> (String, Vec<String>) {
+    let (tree, unique_pool, the_diag) = parse(code);
+    (tree.emit(&unique_pool), describes(the_diag.errors()))
+}
+
+// the expanded tree in S-expression and the errors
+pub fn expand(code:&str) -> (String, Vec<String>) {
+    let (tree, mut unique_pool, mut the_diag) = parse(code);
+    let tree = farvm_tree::expand_v1(tree, &mut unique_pool, &mut the_diag);
+    (tree.emit(&unique_pool), describes(the_diag.errors()))
+}
+
+pub fn items_of(tree:&Node) -> &[Node] {
+    match tree {
+        Node::Module{items, ..} => {items}
+        _ => {panic!("the tree isn't module: {:?}", tree)}
+    }
+}
@@ -0,0 +1,67 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::Node;
+
+#[test]
+fn group_and_methods() {
+    let (tree, errors) = emit("for apply (md ...) {\n    (a:Number) = 1\n    (a:String b:Number) = {2}\n}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(tree, "(module\n  (dispatch apply (md ...)\n    (define (md a:Number) 1)\n    (define (md a:String b:Number) (block\n      2))))");
+}
+
+#[test]
+fn fixed_arity_group() {
+    let (tree, unique_pool, the_diag) = parse("for apply (area) {(s:Shape) = 1}");
+    assert!(!the_diag.has_error(), "{:?}", describes(the_diag.errors()));
+    let Node::DispatchGroup{strategy, name, variadic, methods, ..} = &items_of(&tree)[0] else {
+        panic!("{:?} isn't dispatch group", tree)
+    };
+    assert_eq!((unique_pool.str_at(*strategy), unique_pool.str_at(*name), *variadic), (String::from("apply"), String::from("area"), false));
+    assert_eq!(methods.len(), 1);
+}
+
+#[test]
+fn missing_type_annotation() {
+    let (_, errors) = emit("for apply (md ...) {\n    (a) = 1\n    (a:Number b) = 2\n}");
+    assert_eq!(errors, vec![
+        "parameter 'a' of method 'md' needs a type annotation",
+        "parameter 'b' of method 'md' needs a type annotation",
+    ]);
+}
+
+// the bad group is reported, the items after it are parsed as usual
+#[test]
+fn bad_group_head() {
+    let (tree, errors) = emit("for apply md {(a:Number) = 1}\n(f) = 1");
+    assert_eq!(errors.first().map(|a| a.as_str()), Some("expect '(' but found identifier"));
+    assert!(tree.contains("(define (f) 1)"), "{}", tree);
+}
+
+#[test]
+fn empty_group() {
+    let (tree, errors) = emit("for apply (md ...) {}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(tree, "(module\n  (dispatch apply (md ...)))");
+}
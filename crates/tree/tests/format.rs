@@ -0,0 +1,98 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::TokenValue;
+
+// the pieces of one LiteralFormat, the Expr is it's tokens in S-expression
+fn pieces(code:&str) -> Vec<String> {
+    let (tokens, unique_pool, the_diag) = tokens(code.as_bytes(), false);
+    assert!(!the_diag.has_error(), "{:?}", describes(the_diag.errors()));
+    let [token] = &tokens[..] else {
+        panic!("{} isn't one token", code)
+    };
+    let TokenValue::LiteralFormat(pieces) = &token.val else {
+        panic!("{:?} isn't a format", token.val)
+    };
+    pieces.iter().map(|a| farvm_utils::Emit::emit(a, &unique_pool)).collect()
+}
+
+#[test]
+fn text_and_expr() {
+    assert_eq!(pieces("\"Number:{a}\""), vec!["\"Number:\"", "{(Id a)}"]);
+    assert_eq!(pieces("\"{a}-{(f b)}!\""), vec!["{(Id a)}", "\"-\"", "{( LP0 (Id f) (Id b) ) RP0}", "\"!\""]);
+}
+
+#[test]
+fn nested_brackets_and_strings() {
+    assert_eq!(pieces("\"{[1 {x}]}\""), vec!["{[ LP1 (Integer 1) { LP2 (Id x) } RP2 ] RP1}"]);
+    assert_eq!(pieces("\"a{(f \"s\")}\""), vec!["\"a\"", "{( LP0 (Id f) (String \"s\") ) RP0}"]);
+}
+
+#[test]
+fn escaped_braces_are_text() {
+    assert_eq!(lex("\"{{a}}\""), (vec![TokenValue::LiteralString(String::from("{a}"))], Vec::new()));
+}
+
+#[test]
+fn bad_braces() {
+    assert_eq!(lex_errors("\"a{}b\""), vec!["empty '{}' in string, use '{{' for a '{'"]);
+    assert_eq!(lex_errors("\"a}b\""), vec!["single '}' in string, use '}}' for a '}'"]);
+}
+
+// the closing '"' ends the string, so the code after it is lexed as usual
+#[test]
+fn unclosed_brace_at_quote() {
+    let (tokens, errors) = lex("\"{\" (f x)");
+    assert_eq!(errors, vec!["unclosed '{' in string"]);
+    assert_eq!(tokens.len(), 5);
+    assert!(matches!(&tokens[0], TokenValue::LiteralFormat(a) if a.len() == 1));
+    let (tokens, errors) = lex("\"a{x\" y");
+    assert_eq!(errors, vec!["unclosed '{' in string"]);
+    assert_eq!(tokens.len(), 2);
+}
+
+// the string is ended with the line, so the next define is lexed as code
+#[test]
+fn unclosed_brace_at_line_end() {
+    let (tokens, errors) = lex("(f) = \"a{x\n(g) = 1");
+    assert_eq!(errors, vec!["unclosed '{' in string"]);
+    assert_eq!(tokens.len(), 10);
+    let (tree, errors) = emit("(f) = \"a{x\n(g) = 1");
+    assert_eq!(errors, vec!["unclosed '{' in string"]);
+    assert!(tree.contains("(define (g) 1)"), "{}", tree);
+}
+
+#[test]
+fn unclosed_brace_before_comment() {
+    let (tokens, errors) = lex("\"{x # c\n(g) = 1");
+    assert_eq!(errors, vec!["unclosed '{' in string"]);
+    assert_eq!(tokens.len(), 6);
+}
+
+#[test]
+fn format_node() {
+    let (tree, errors) = emit("(f a) = \"a={a}\"");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(format \"a=\" a)"), "{}", tree);
+}
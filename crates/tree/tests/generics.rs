@@ -0,0 +1,63 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::TokenValue;
+
+#[test]
+fn generic_define() {
+    let (tree, errors) = emit("(map<T U> f:(T -> U) xs:List<T>) = xs");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(define (map<T U> f:(-> T U) xs:(generic List T)) xs)"), "{}", tree);
+}
+
+#[test]
+fn nested_type_application() {
+    let (tree, errors) = emit("x:Map<String List<Number>> = 1");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(= x:(generic Map String (generic List Number)) 1)"), "{}", tree);
+}
+
+#[test]
+fn function_types() {
+    let (tree, errors) = emit("(h f:(-> Number) g:(Number Number -> Bool)) = f");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(define (h f:(-> Number) g:(-> Number Number Bool)) f)"), "{}", tree);
+}
+
+// the '<' and '>' are comparison in expression, the "<-" is ASSIGN
+#[test]
+fn comparison_and_assign() {
+    let (tree, errors) = emit("(g a b) = {\n    a <- b\n    [(< a b) (<= a b) (> a b) (>= a b)]\n}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(<- a b)"), "{}", tree);
+    assert!(tree.contains("(list (call < a b) (call <= a b) (call > a b) (call >= a b))"), "{}", tree);
+    let (tokens, _) = lex("a<-b a <b");
+    assert_eq!(tokens[1], TokenValue::ASSIGN);
+    assert_eq!(tokens[4], TokenValue::LP3);
+}
+
+#[test]
+fn bad_generics() {
+    assert_eq!(emit("(f<T> x:List<T) = x").1.first().map(|a| a.as_str()), Some("expect '>' but found ')'"));
+}
@@ -0,0 +1,166 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use farvm_utils::{diag, pool, source};
+
+fn json(code:&str) -> String {
+    let mut the_source = source::make();
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let file = the_source.add_file("main.fa", code.as_bytes().to_vec());
+    let tree = farvm_tree::build_v1(the_source.code(file), file, &mut unique_pool, &mut the_diag);
+    assert!(!the_diag.has_error());
+    farvm_tree::json_v1(&tree, &unique_pool, &the_source)
+}
+
+// the keys are in the documented order, the start and end are bytes but the column is chars
+#[test]
+fn snapshot() {
+    assert_eq!(json("x = 1u8\n(f a) = (g \"é\" a)"), SNAPSHOT);
+}
+
+#[test]
+fn strings_are_escaped() {
+    let text = json("x = \"a\\\"b\\n\"");
+    assert!(text.contains("\"value\": \"a\\\"b\\n\""), "{}", text);
+}
+
+const SNAPSHOT:&str = r#"{
+  "format": "farvm-tree",
+  "version": 1,
+  "files": [
+    {
+      "id": 0,
+      "name": "main.fa"
+    }
+  ],
+  "tree": {
+    "kind": "Module",
+    "span": {
+      "file": 0,
+      "start": 0,
+      "end": 26,
+      "line": 1,
+      "column": 1
+    },
+    "items": [
+      {
+        "kind": "Assign",
+        "span": {
+          "file": 0,
+          "start": 0,
+          "end": 7,
+          "line": 1,
+          "column": 1
+        },
+        "name": "x",
+        "type": null,
+        "value": {
+          "kind": "Literal",
+          "span": {
+            "file": 0,
+            "start": 4,
+            "end": 7,
+            "line": 1,
+            "column": 5
+          },
+          "type": "integer",
+          "value": "1",
+          "suffix": "u8"
+        }
+      },
+      {
+        "kind": "Define",
+        "span": {
+          "file": 0,
+          "start": 8,
+          "end": 26,
+          "line": 2,
+          "column": 1
+        },
+        "name": "f",
+        "generics": [],
+        "params": [
+          {
+            "kind": "Param",
+            "span": {
+              "file": 0,
+              "start": 11,
+              "end": 12,
+              "line": 2,
+              "column": 4
+            },
+            "name": "a",
+            "type": null
+          }
+        ],
+        "body": {
+          "kind": "Call",
+          "span": {
+            "file": 0,
+            "start": 16,
+            "end": 26,
+            "line": 2,
+            "column": 9
+          },
+          "callee": {
+            "kind": "Ident",
+            "span": {
+              "file": 0,
+              "start": 17,
+              "end": 18,
+              "line": 2,
+              "column": 10
+            },
+            "name": "g"
+          },
+          "args": [
+            {
+              "kind": "Literal",
+              "span": {
+                "file": 0,
+                "start": 19,
+                "end": 23,
+                "line": 2,
+                "column": 12
+              },
+              "type": "string",
+              "value": "é",
+              "suffix": ""
+            },
+            {
+              "kind": "Ident",
+              "span": {
+                "file": 0,
+                "start": 24,
+                "end": 25,
+                "line": 2,
+                "column": 16
+              },
+              "name": "a"
+            }
+          ]
+        }
+      }
+    ]
+  }
+}"#;
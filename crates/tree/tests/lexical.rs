@@ -0,0 +1,76 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+#[test]
+fn valid_code_has_no_error() {
+    let (tokens, errors) = lex("(main) = {(println! \"a{x}\" 'c' 1i32 2.5)}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(tokens.len(), 13);
+}
+
+#[test]
+fn unterminated_string() {
+    assert_eq!(lex_errors("(f \"abc)"), vec!["unterminated string, lack of '\"'"]);
+}
+
+#[test]
+fn unterminated_char() {
+    assert_eq!(lex_errors("(f 'a)"), vec!["missing closing ' of char literal"]);
+}
+
+#[test]
+fn unknown_escape() {
+    assert_eq!(lex_errors("\"a\\qb\""), vec!["unknown escape '\\q' in string"]);
+}
+
+#[test]
+fn braces_in_string() {
+    assert_eq!(lex_errors("\"a{}b\""), vec!["empty '{}' in string, use '{{' for a '{'"]);
+    assert_eq!(lex_errors("\"a}b\""), vec!["single '}' in string, use '}}' for a '}'"]);
+}
+
+#[test]
+fn bad_number() {
+    assert_eq!(lex_errors("12x4"), vec!["number '12x4' has invalid suffix 'x4'"]);
+    assert_eq!(lex_errors("300u8"), vec!["number '300u8' out of range for 'u8' (0..=255)"]);
+}
+
+#[test]
+fn invalid_utf8() {
+    let (_, _, the_diag) = tokens(b"(f \xFF)", false);
+    let errors = describes(the_diag.errors());
+    assert_eq!(errors, vec!["invalid UTF-8 sequence [FF]"]);
+}
+
+#[test]
+fn stray_control_character() {
+    assert_eq!(lex_errors("(f \u{7})"), vec!["stray control character U+0007"]);
+}
+
+// the lexer go on after an error, so the errors of one run are all reported
+#[test]
+fn many_errors_in_one_run() {
+    assert_eq!(lex_errors("\"\\q\" \u{7} \"a}\"").len(), 3);
+}
@@ -0,0 +1,87 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::{Lexer, Token, TokenValue, TriviaKind};
+use farvm_utils::{diag, pool};
+
+// the tokens with the _EOF, it's hold the trivia at end
+fn all_tokens(code:&str) -> Vec<Token> {
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let mut lexer = farvm_tree::make_lexer_v1(code.as_bytes(), 0, true);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.lexer_pass(&mut unique_pool, &mut the_diag);
+        let end = token.val == TokenValue::_EOF;
+        tokens.push(token);
+        if end {
+            break tokens
+        }
+    }
+}
+
+// the full spans of tokens are joined to the code again
+fn round_trip(code:&str) {
+    let text:Vec<u8> = all_tokens(code).iter().flat_map(|a| {
+        let span = a.full_span();
+        code.as_bytes()[span.start as usize..span.end as usize].to_vec()
+    }).collect();
+    assert_eq!(String::from_utf8(text).unwrap(), code);
+}
+
+#[test]
+fn byte_for_byte() {
+    round_trip("(f a) = {\n  # the body\n  (g a)\t\r\n}\n");
+    round_trip("## doc\n(main) = \"a{x}b\" 'c' r#\"raw\"#   ");
+    round_trip("  \n\n");
+    round_trip("");
+}
+
+// the bad chars are trivia too, so the text isn't lost
+#[test]
+fn skipped_chars() {
+    round_trip("(f \u{7} x)");
+    let tokens = all_tokens("(f \u{7} x)");
+    assert!(tokens.iter().flat_map(|a| &a.trivia).any(|a| a.kind == TriviaKind::Skipped));
+}
+
+#[test]
+fn trivia_kinds() {
+    let code = "## doc\n# note\nx";
+    let tokens = all_tokens(code);
+    let kinds:Vec<(TriviaKind, &str)> = tokens[0].trivia.iter()
+        .map(|a| (a.kind, &code[a.span.start as usize..a.span.end as usize])).collect();
+    assert_eq!(kinds, vec![
+        (TriviaKind::DocComment, "## doc"),
+        (TriviaKind::Whitespace, "\n"),
+        (TriviaKind::Comment, "# note"),
+        (TriviaKind::Whitespace, "\n"),
+    ]);
+}
+
+#[test]
+fn no_trivia_by_default() {
+    let (tokens, _, _) = tokens(b"# note\n(f) = 1 # end", false);
+    assert!(tokens.iter().all(|a| a.trivia.is_empty()));
+}
@@ -0,0 +1,63 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+// the local of macro is renamed, so it can't be same as the names of caller
+#[test]
+fn bound_names_are_fresh() {
+    let (tree, errors) = expand("(swap! a b) = {\n    t = a\n    a <- b\n    b <- t\n}\n(main) = {\n    t = 1\n    u = 2\n    (swap! t u)\n}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(= t{1} t)"), "{}", tree);
+    assert!(tree.contains("(<- t u)"), "{}", tree);
+    assert!(tree.contains("(<- u t{1})"), "{}", tree);
+}
+
+// the free name of macro is the name at define site, the local of caller is renamed
+#[test]
+fn free_names_are_not_captured() {
+    let (tree, errors) = expand("(helper) = \"global\"\n(call_helper!) = (helper)\n(main) = {\n    helper = \"local\"\n    [(call_helper!) helper]\n}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(= helper{2} \"local\")"), "{}", tree);
+    assert!(tree.contains("(list (call helper) helper{2})"), "{}", tree);
+}
+
+#[test]
+fn rest_parameter_is_spliced() {
+    let (tree, errors) = expand("(all! xs...) = [xs]\n(main) = (all! 1 2 3)");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(list 1 2 3)"), "{}", tree);
+}
+
+#[test]
+fn depth_limit() {
+    let (tree, errors) = expand("(loop! x) = (loop! x)\n(main) = (loop! 1)");
+    assert_eq!(errors, vec!["macro 'loop!' is expanded too deep, more than 64"]);
+    assert!(tree.contains("(error)"), "{}", tree);
+}
+
+#[test]
+fn arity_of_macro() {
+    let (_, errors) = expand("(one! x) = x\n(main) = (one! 1 2)");
+    assert_eq!(errors, vec!["macro 'one!' expects 1 argument(s) but found 2"]);
+}
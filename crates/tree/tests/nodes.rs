@@ -0,0 +1,81 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+
+// the emit of the only item
+fn item(code:&str) -> String {
+    let (tree, unique_pool, the_diag) = parse(code);
+    assert!(!the_diag.has_error(), "{}: {:?}", code, describes(the_diag.errors()));
+    match items_of(&tree) {
+        [a] => {farvm_utils::Emit::emit(a, &unique_pool)}
+        items => {panic!("{} isn't one item: {:?}", code, items)}
+    }
+}
+
+#[test]
+fn module_items() {
+    assert_eq!(item("module geo.shapes"), "(module-name geo.shapes)");
+    assert_eq!(item("import a.b as c {x y}"), "(import a.b as c (x y))");
+    assert_eq!(item("import a"), "(import a)");
+    assert_eq!(item("pub x:Number = 1"), "(pub (= x:Number 1))");
+}
+
+#[test]
+fn define_and_params() {
+    assert_eq!(item("(f a b:Number) = a"), "(define (f a b:Number) a)");
+    assert_eq!(item("(f) = {}"), "(define (f) (block))");
+    assert_eq!(item("(m! a rest ...) = a"), "(define-macro (m! a rest ...) a)");
+}
+
+#[test]
+fn literals() {
+    assert_eq!(item("x = [1 2.5 -3i8 \"s\" 'c' true false nil]"), "(= x (list 1 2.5 -3i8 \"s\" 'c' true false nil))");
+    assert_eq!(item("x = \"a\\n\\\"b\\\"\""), "(= x \"a\\n\\\"b\\\"\")");
+}
+
+#[test]
+fn expressions() {
+    assert_eq!(item("(f a) = (g a.b)"), "(define (f a) (call g (. a b)))");
+    assert_eq!(item("(f a) = ((g a) 1)"), "(define (f a) (call (call g a) 1))");
+    assert_eq!(item("(f a) = (println! \"v={a}\")"), "(define (f a) (macro println! (format \"v=\" a)))");
+    assert_eq!(item("(f a) = {a <- 2}"), "(define (f a) (block\n  (<- a 2)))");
+    assert_eq!(item("(f) = {y:Number = 1}"), "(define (f) (block\n  (= y:Number 1)))");
+}
+
+#[test]
+fn objects() {
+    assert_eq!(item("o = @{a: 1 b: 2}"), "(= o (object (slot a 1) (slot b 2)))");
+    assert_eq!(item("o = @p{c: 3}"), "(= o (object @p (slot c 3)))");
+}
+
+// the id is of the node in this tree, a clone has its own ids
+#[test]
+fn node_ids() {
+    let (tree, _, _) = parse("(f a) = a\n(g) = 1");
+    let items = items_of(&tree);
+    assert_eq!(items[0].id(), items[0].id());
+    assert_ne!(items[0].id(), items[1].id());
+    let copy = tree.clone();
+    assert_ne!(items_of(&copy)[0].id(), items[0].id());
+}
@@ -0,0 +1,88 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::{NumberSuffix, TokenValue};
+
+// the number of one token as (is-float, digits in pool, suffix)
+fn number(code:&str) -> (bool, String, NumberSuffix) {
+    let (tokens, unique_pool, the_diag) = tokens(code.as_bytes(), false);
+    assert!(!the_diag.has_error(), "{:?}", describes(the_diag.errors()));
+    match &tokens[..] {
+        [a] => {
+            match a.val {
+                TokenValue::LiteralInteger(id, suffix) => {(false, unique_pool.str_at(id), suffix)}
+                TokenValue::LiteralFloat(id, suffix) => {(true, unique_pool.str_at(id), suffix)}
+                _ => {panic!("{:?} isn't a number", a.val)}
+            }
+        }
+        _ => {panic!("{} isn't one token", code)}
+    }
+}
+
+#[test]
+fn radix_prefixes() {
+    assert_eq!(number("0xFF"), (false, String::from("255"), NumberSuffix::None));
+    assert_eq!(number("0b1010"), (false, String::from("10"), NumberSuffix::None));
+    assert_eq!(number("0o17"), (false, String::from("15"), NumberSuffix::None));
+    assert_eq!(number("0xFFu8"), (false, String::from("255"), NumberSuffix::U8));
+}
+
+#[test]
+fn separators() {
+    assert_eq!(number("1_000_000"), (false, String::from("1000000"), NumberSuffix::None));
+    assert_eq!(number("0b1111_0000"), (false, String::from("240"), NumberSuffix::None));
+}
+
+#[test]
+fn sign_exponent_and_suffix() {
+    assert_eq!(number("-255"), (false, String::from("-255"), NumberSuffix::None));
+    assert_eq!(number("1.5e3"), (true, String::from("1.5e3"), NumberSuffix::None));
+    assert_eq!(number("2.5f32"), (true, String::from("2.5"), NumberSuffix::F32));
+    assert_eq!(number("3f64"), (true, String::from("3"), NumberSuffix::F64));
+    assert_eq!(number("1i32"), (false, String::from("1"), NumberSuffix::I32));
+}
+
+#[test]
+fn suffix_range() {
+    assert_eq!(number("255u8"), (false, String::from("255"), NumberSuffix::U8));
+    assert_eq!(number("-128i8"), (false, String::from("-128"), NumberSuffix::I8));
+    assert_eq!(lex_errors("256u8"), vec!["number '256u8' out of range for 'u8' (0..=255)"]);
+    assert_eq!(lex_errors("-129i8"), vec!["number '-129i8' out of range for 'i8' (-128..=127)"]);
+}
+
+#[test]
+fn malformed() {
+    assert_eq!(lex_errors("1.2.3"), vec!["number '1.2.3' has invalid suffix '.3'"]);
+    assert_eq!(lex_errors("0x"), vec!["number '0x' lack of digits"]);
+    assert_eq!(lex_errors("1e"), vec!["number '1e' lack of digits in exponent"]);
+    assert_eq!(lex_errors("1.0i32"), vec!["number '1.0i32' is float but suffix 'i32' is integer"]);
+}
+
+// the bad number is still one token, so the tokens after it are right
+#[test]
+fn bad_number_is_one_token() {
+    let (tokens, errors) = lex("(f 1.2.3 x)");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(tokens.len(), 5);
+}
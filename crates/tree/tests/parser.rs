@@ -0,0 +1,84 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::Node;
+
+// the names of top level defines and the errors
+fn defines(code:&str) -> (Vec<String>, Vec<String>) {
+    let (tree, unique_pool, the_diag) = parse(code);
+    let names = items_of(&tree).iter().filter_map(|a| match a {
+        Node::Define{name, ..} => {Some(unique_pool.str_at(*name))}
+        _ => {None}
+    }).collect();
+    (names, describes(the_diag.errors()))
+}
+
+#[test]
+fn valid_code_has_no_error() {
+    let (names, errors) = defines("(f x) = (+ x 1)\n(main) = {(f 1)}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(names, vec!["f", "main"]);
+}
+
+// the bad item is reported, the items after it are parsed as usual
+#[test]
+fn recover_after_bad_item() {
+    let (names, errors) = defines("(ok a) = {1}\n(bad a b = {1}\n(f x) = (+ x 1)\n(main) = (f 1)");
+    assert_eq!(names, vec!["ok", "f", "main"]);
+    assert_eq!(errors, vec!["unexpected '='", "expect ')' but found '('"]);
+}
+
+// the define at line start ends the unclosed delimiters before it
+#[test]
+fn resync_at_define_inside_unclosed() {
+    let (names, errors) = defines("(g y) = {\n    (f [y 1\n(f x) = (+ x 1)\n(main) = (g 1)");
+    assert_eq!(names, vec!["g", "f", "main"]);
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    assert!(errors.iter().all(|a| a.starts_with("expect ")), "{:?}", errors);
+}
+
+#[test]
+fn many_errors_in_one_run() {
+    let (names, errors) = defines("(a) = (\n(b) = )\n(c) = ]\n(d) = 1");
+    assert!(errors.len() >= 3, "{:?}", errors);
+    assert!(names.contains(&String::from("d")), "{:?}", names);
+}
+
+// the define in block is reported once by parser, and it isn't a global
+#[test]
+fn define_in_block() {
+    let (names, errors) = defines("(f x) = {\n    (g y) = (+ y 1)\n    (g x)\n}\n(main) = (f 1)");
+    assert_eq!(names, vec!["f", "main"]);
+    assert_eq!(errors, vec!["'g' should be defined at top level"]);
+    let (tree, errors) = emit("(f) = {for apply (h ...) {(a:Number) = a} 1}");
+    assert_eq!(errors, vec!["'h' should be defined at top level"]);
+    assert!(tree.contains("(block\n    (= h (error))\n    1)"), "{}", tree);
+}
+
+// the assign in block is a local, it's not a define
+#[test]
+fn assign_in_block() {
+    let (_, errors) = defines("(f) = {\n    g = 1\n    g <- 2\n    g\n}");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
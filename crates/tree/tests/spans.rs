@@ -0,0 +1,76 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::Node;
+use farvm_utils::source;
+
+// the (start, end) of tokens
+fn spans(code:&str) -> Vec<(u32, u32)> {
+    let (tokens, _, _) = tokens(code.as_bytes(), false);
+    tokens.iter().map(|a| (a.span.start, a.span.end)).collect()
+}
+
+#[test]
+fn token_spans_are_byte_ranges() {
+    assert_eq!(spans("(f 12 \"ab\")"), vec![(0, 1), (1, 2), (3, 5), (6, 10), (10, 11)]);
+    assert_eq!(spans("é x"), vec![(0, 2), (3, 4)]);
+}
+
+#[test]
+fn node_span_covers_children() {
+    let code = "(f a) = {(g a)}\n(h) = 1";
+    let (tree, _, the_diag) = parse(code);
+    assert!(!the_diag.has_error(), "{:?}", describes(the_diag.errors()));
+    let items = items_of(&tree);
+    assert_eq!(&code[items[0].span().start as usize..items[0].span().end as usize], "(f a) = {(g a)}");
+    assert_eq!(&code[items[1].span().start as usize..items[1].span().end as usize], "(h) = 1");
+    let Node::Define{body, ..} = &items[0] else {
+        panic!("{:?} isn't define", items[0])
+    };
+    assert_eq!((body.span().start, body.span().end), (8, 15));
+    for a in body.children() {
+        assert!(body.span().start <= a.span().start && a.span().end <= body.span().end);
+    }
+}
+
+// the col is start at 1 on every line
+#[test]
+fn rows_and_cols() {
+    let mut the_source = source::make();
+    let file = the_source.add_file("main.fa", b"(f) = 1\n  (g) = x\r\nh".to_vec());
+    assert_eq!(the_source.row_col(file, 0), (1, 1));
+    assert_eq!(the_source.row_col(file, 7), (1, 8));
+    assert_eq!(the_source.row_col(file, 8), (2, 1));
+    assert_eq!(the_source.row_col(file, 10), (2, 3));
+    assert_eq!(the_source.row_col(file, 19), (3, 1));
+    assert_eq!(the_source.locate(farvm_utils::Span::new(file, 16, 17)), "main.fa:2:9");
+}
+
+#[test]
+fn render_marks_the_span() {
+    let mut the_source = source::make();
+    let file = the_source.add_file("main.fa", b"(f) = 1\n(g) = \"abc".to_vec());
+    let (_, _, the_diag) = tokens(the_source.code(file), false);
+    assert_eq!(the_diag.render(&the_source), "error main.fa:2:7: unterminated string, lack of '\"'\n    | (g) = \"abc\n    |       ^\n1 error(s), 0 warning(s)");
+}
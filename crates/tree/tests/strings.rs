@@ -0,0 +1,74 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::TokenValue;
+
+// the text of the only string token, and the span of it
+fn string_of(code:&str) -> (String, (u32, u32)) {
+    let (tokens, _, the_diag) = tokens(code.as_bytes(), false);
+    assert!(!the_diag.has_error(), "{}: {:?}", code, describes(the_diag.errors()));
+    match &tokens[..] {
+        [token] => {match &token.val {
+            TokenValue::LiteralString(a) => {(a.clone(), (token.span.start, token.span.end))}
+            a => {panic!("{} isn't string: {:?}", code, a)}
+        }}
+        _ => {panic!("{} isn't one token", code)}
+    }
+}
+
+#[test]
+fn raw_string_has_no_escape() {
+    assert_eq!(string_of("r\"C:\\dir\\n{x}\""), (String::from("C:\\dir\\n{x}"), (0, 14)));
+    assert_eq!(string_of("r#\"say \"hi\"\"#"), (String::from("say \"hi\""), (0, 13)));
+    assert_eq!(string_of("r##\"a\"#b\"##"), (String::from("a\"#b"), (0, 11)));
+}
+
+// the "r" and "r#x" without '"' are Id
+#[test]
+fn raw_prefix_is_id() {
+    let (tokens, errors) = lex("r r#x");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(tokens.len(), 2);
+    assert!(tokens.iter().all(|a| matches!(a, TokenValue::Id(_))));
+}
+
+#[test]
+fn triple_string_strips_indent() {
+    let code = "\"\"\"\n    SELECT *\n      FROM t\n    \"\"\"";
+    assert_eq!(string_of(code), (String::from("SELECT *\n  FROM t"), (0, code.len() as u32)));
+    assert_eq!(string_of("\"\"\"a \"quoted\" \\n\"\"\""), (String::from("a \"quoted\" \\n"), (0, 19)));
+    assert_eq!(string_of("\"\"\"\n  a\n\n  b\n\"\"\""), (String::from("a\n\nb"), (0, 16)));
+}
+
+#[test]
+fn empty_string_isnt_triple() {
+    assert_eq!(lex("\"\" x").0.len(), 2);
+    assert_eq!(string_of("\"\""), (String::new(), (0, 2)));
+}
+
+#[test]
+fn unterminated() {
+    assert_eq!(lex_errors("r#\"abc\""), vec!["unterminated string, lack of '\"#'"]);
+    assert_eq!(lex_errors("\"\"\"abc\"\""), vec!["unterminated string, lack of '\"\"\"'"]);
+}
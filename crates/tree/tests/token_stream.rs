@@ -0,0 +1,102 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::{token_stream, TokenValue};
+use farvm_utils::{diag, pool, Emit};
+
+// run f with a stream of code, the errors are returned after
+fn with_stream(code:&str, f:impl FnOnce(&mut token_stream::Object<'_, token_stream::Replay>)) -> Vec<String> {
+    let (tokens, _, _) = tokens(code.as_bytes(), false);
+    let end = tokens.last().map(|a| a.span).unwrap_or_default();
+    let mut lexer = token_stream::replay(tokens, end);
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let mut stream = token_stream::make(&mut lexer, &mut unique_pool, &mut the_diag);
+    f(&mut stream);
+    describes(the_diag.errors())
+}
+
+#[test]
+fn peek_ahead_without_bump() {
+    let errors = with_stream("(f x)", |stream| {
+        assert!(stream.peek_is(0, &TokenValue::LP0));
+        assert!(stream.peek_is(3, &TokenValue::RP0));
+        assert!(stream.peek_is(0, &TokenValue::LP0));
+        assert_eq!(stream.bump().val, TokenValue::LP0);
+        assert!(stream.peek_is(0, &TokenValue::Id(Default::default())));
+    });
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+// the _EOF is repeat, and bump at end don't move
+#[test]
+fn eof_repeats() {
+    let errors = with_stream("x", |stream| {
+        stream.bump();
+        assert!(stream.at_eof());
+        assert!(stream.peek_is(5, &TokenValue::_EOF));
+        assert_eq!(stream.bump().val, TokenValue::_EOF);
+        assert!(stream.at_eof());
+    });
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn eat_and_expect() {
+    let errors = with_stream("(x", |stream| {
+        assert!(stream.eat(&TokenValue::RP0).is_none());
+        assert!(stream.eat(&TokenValue::LP0).is_some());
+        assert!(stream.expect(&TokenValue::COLON).is_none());
+        assert!(stream.expect(&TokenValue::Id(Default::default())).is_some());
+        assert!(stream.expect(&TokenValue::RP0).is_none());
+    });
+    assert_eq!(errors, vec!["expect ':' but found identifier", "expect ')' but found end of file"]);
+}
+
+#[test]
+fn checkpoint_and_rewind() {
+    let errors = with_stream("(f x) = 1", |stream| {
+        let at = stream.checkpoint();
+        let seen:Vec<TokenValue> = (0..4).map(|_| stream.bump().val).collect();
+        assert_eq!(seen[3], TokenValue::RP0);
+        assert!(stream.peek_is(0, &TokenValue::EQUAL));
+        stream.rewind(at);
+        assert_eq!(stream.checkpoint(), at);
+        assert_eq!(stream.bump().val, TokenValue::LP0);
+        assert_eq!(stream.prev_span().start, 0);
+    });
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+// the stream works over the lexer_v1 too, it's share the pool
+#[test]
+fn over_lexer_v1() {
+    let mut unique_pool = pool::make();
+    let mut the_diag = diag::make();
+    let mut lexer = farvm_tree::make_lexer_v1(b"(add 1 2)", 0, false);
+    let mut stream = token_stream::make(&mut lexer, &mut unique_pool, &mut the_diag);
+    let name = stream.peek(1).val.clone();
+    assert!(stream.peek_is(4, &TokenValue::RP0));
+    assert_eq!(name.emit(stream.pool()), "(Id add)");
+}
@@ -0,0 +1,56 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_utils::Emit;
+
+// the emit of tokens and the errors
+fn emits(code:&str) -> (Vec<String>, Vec<String>) {
+    let (tokens, unique_pool, the_diag) = tokens(code.as_bytes(), false);
+    (tokens.iter().map(|a| a.val.emit(&unique_pool)).collect(), describes(the_diag.errors()))
+}
+
+#[test]
+fn ellipsis_and_dots() {
+    assert_eq!(emits("(md ...)").0, vec!["( LP0", "(Id md)", "... ELLIPSIS", ") RP0"]);
+    assert_eq!(emits("a.b").0, vec!["(Id a)", ". DOT", "(Id b)"]);
+    assert_eq!(emits("..").0, vec![". DOT", ". DOT"]);
+    assert_eq!(emits("....").0, vec!["... ELLIPSIS", ". DOT"]);
+}
+
+#[test]
+fn macro_id() {
+    assert_eq!(emits("(println! x)").0, vec!["( LP0", "(MacroId println)", "(Id x)", ") RP0"]);
+    assert_eq!(emits("!").0, vec!["(Id !)"]);
+    assert_eq!(emits("a!b").0, vec!["(Id a!b)"]);
+}
+
+// the parser tell the variadic param, member access and macro call apart
+#[test]
+fn in_tree() {
+    let (tree, errors) = emit("(log! args ...) = {(println! args)}\n(f o) = o.x");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(tree.contains("(define-macro (log! args ...)"), "{}", tree);
+    assert!(tree.contains("(macro println! args)"), "{}", tree);
+    assert!(tree.contains("(. o x)"), "{}", tree);
+}
@@ -0,0 +1,70 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::TokenValue;
+use farvm_utils::source::{self, ColumnUnit};
+
+// the names of Id tokens and the errors
+fn names(code:&str) -> (Vec<String>, Vec<String>) {
+    let (tokens, unique_pool, the_diag) = tokens(code.as_bytes(), false);
+    let names = tokens.iter().filter_map(|a| match a.val {
+        TokenValue::Id(id) => {Some(unique_pool.str_at(id))}
+        _ => {None}
+    }).collect();
+    (names, describes(the_diag.errors()))
+}
+
+#[test]
+fn xid_identifiers() {
+    let (found, errors) = names("café 日本語 变量_1 αβγ ℕ e\u{301}");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(found, vec!["café", "日本語", "变量_1", "αβγ", "ℕ", "e\u{301}"]);
+}
+
+// the combining mark is XID_Continue but not XID_Start, the '²' and emoji are neither
+#[test]
+fn not_xid_characters() {
+    assert_eq!(names("\u{301}x"), (vec![String::from("x")], vec![String::from("character '\u{301}' (U+0301) can't be in identifier")]));
+    assert_eq!(names("x²").1, vec!["character '²' (U+00B2) can't be in identifier"]);
+    assert_eq!(names("a😀").1, vec!["character '😀' (U+1F600) can't be in identifier"]);
+}
+
+#[test]
+fn utf8_in_string() {
+    let (tokens, errors) = lex("\"héllo😀\"");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(tokens, vec![TokenValue::LiteralString(String::from("héllo😀"))]);
+}
+
+// the '😀' is 1 char but 2 code units of UTF-16
+#[test]
+fn column_units() {
+    let mut the_source = source::make();
+    let file = the_source.add_file("test.fa", "(f \"😀\" x)".as_bytes().to_vec());
+    let (tokens, _, _) = tokens(the_source.code(file), false);
+    let x = tokens[3].span;
+    assert_eq!(the_source.start_of(x), (1, 8));
+    the_source.set_column_unit(ColumnUnit::Utf16);
+    assert_eq!(the_source.start_of(x), (1, 9));
+}
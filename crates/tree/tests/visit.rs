@@ -0,0 +1,91 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+mod common;
+use common::*;
+use farvm_tree::{visit, Folder, Node, Visitor};
+
+// the code has every variant of Node except _Empty, the (bad a b = 1) is an Error
+const ALL:&str = "module m
+import a.b as c {x}
+pub (map<T> f:(T -> T) xs:List<T> y:auto) = {
+    z:Number = [1 \"s{y}\" (f y) xs.head @xs{k: 1}]
+    z <- 2
+    (log! z)
+}
+(log! a rest ...) = a
+for apply (md ...) {(a:Number) = a}
+(bad a b = 1
+";
+
+// the kind and span of node, they're same for a node and its clone
+fn describe(node:&Node) -> String {
+    let kind = format!("{:?}", node);
+    let kind = kind.split(['{', ' ']).next().unwrap_or("");
+    format!("{} {}", kind, node.span())
+}
+
+struct Seen(Vec<String>);
+
+impl Visitor<'_> for Seen {
+    fn visit(&mut self, node:&Node) {
+        self.0.push(describe(node));
+        visit::walk(self, node)
+    }
+}
+impl Folder for Seen {
+    fn fold(&mut self, node:Node) -> Node {
+        self.0.push(describe(&node));
+        visit::fold_children(self, node)
+    }
+}
+
+#[test]
+fn folder_reaches_what_visitor_reaches() {
+    let (tree, _, _) = parse(ALL);
+    let mut visited = Seen(Vec::new());
+    visited.visit(&tree);
+    let mut folded = Seen(Vec::new());
+    let again = folded.fold(tree.clone());
+    assert_eq!(folded.0, visited.0);
+    assert_eq!(again, tree);
+    let mut kinds:Vec<&str> = visited.0.iter().map(|a| a.split(' ').next().unwrap_or("")).collect();
+    kinds.sort();
+    kinds.dedup();
+    assert_eq!(kinds.len(), 26, "{:?}", kinds);
+}
+
+// the params of macro are children too, so a folder can rename them
+#[test]
+fn macro_params_are_folded() {
+    struct Count(usize);
+    impl Folder for Count {
+        fn fold_param(&mut self, node:Node) -> Node {
+            self.0 += 1;
+            node
+        }
+    }
+    let (tree, _, _) = parse("(log! a rest ...) = [a rest]");
+    let mut count = Count(0);
+    count.fold(tree);
+    assert_eq!(count.0, 2);
+}
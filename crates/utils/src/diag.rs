@@ -21,21 +21,52 @@
 // SOFTWARE.
 
 use core::fmt;
+use super::source::{Object as Source, Span};
 
 #[derive(Debug,Default)]
 #[allow(dead_code)]
 pub struct DiagUnit {
-    row: u32,
-    col: u32,
+    span: Span,
     describe: String,
+    notes: Vec<(Span, String)>,
 }
 impl DiagUnit {
-    pub fn row(&self) -> u32 {self.row}
-    pub fn col(&self) -> u32 {self.col}
+    pub fn span(&self) -> Span {self.span}
     pub fn describe(&self) -> String {self.describe.clone()}
+    pub fn notes(&self) -> &[(Span, String)] {&self.notes}
+
+    // the other place about this, like the opening of unclosed delimiter
+    pub fn note(&mut self, span:Span, describe:&str) -> &mut DiagUnit {
+        self.notes.push((span, String::from(describe)));
+        self
+    }
+
+    // like "main.fa:3:5: describe" and the line of source with '^' under the span, then the notes
+    pub fn render(&self, source:&Source) -> String {
+        let mut text = render_at(source, self.span, &self.describe);
+        for (span, describe) in &self.notes {
+            text += &format!("\nnote {}", render_at(source, *span, describe));
+        }
+        text
+    }
 }
 
-#[derive(Debug)]
+fn render_at(source:&Source, span:Span, describe:&str) -> String {
+    let (row, col) = source.start_of(span);
+    let code = source.code(span.file);
+    let start = (span.start as usize).min(code.len());
+    let line_start = code[..start].iter().rposition(|&a| a == b'\n').map_or(0, |a| a + 1);
+    let line_end = code[start..].iter().position(|&a| a == b'\n').map_or(code.len(), |a| start + a);
+    let line = String::from_utf8_lossy(&code[line_start..line_end]);
+    let line = line.trim_end_matches('\r');
+    let marks = source.text_of(span).lines().next().map_or(1, |a| a.chars().count().max(1));
+    let indent:String = String::from_utf8_lossy(&code[line_start..start]).chars()
+        .map(|a| if a == '\t' {'\t'} else {' '}).collect();
+    format!("{}:{}:{}: {}\n    | {}\n    | {}{}",
+        source.name(span.file), row, col, describe, line, indent, "^".repeat(marks))
+}
+
+#[derive(Debug,Default)]
 #[allow(dead_code)]
 pub struct Object {
     warnings: Vec<DiagUnit>,
@@ -46,16 +77,58 @@ impl Object {
     pub fn new() -> Object {
         Object { warnings: Vec::new(), errors: Vec::new() }
     }
-    pub fn has_error(&self) -> bool {self.errors.len() > 0}
-    pub fn push_error(&mut self, row:u32, col:u32, describe:&str) {
-        self.errors.push(DiagUnit { row, col, describe: String::from(describe) })
+    pub fn has_error(&self) -> bool {!self.errors.is_empty()}
+    pub fn errors(&self) -> &[DiagUnit] {&self.errors}
+    pub fn warnings(&self) -> &[DiagUnit] {&self.warnings}
+    // the pushed unit is returned for add notes
+    pub fn push_error(&mut self, span:Span, describe:&str) -> &mut DiagUnit {
+        self.errors.push(DiagUnit { span, describe: String::from(describe), notes: Vec::new() });
+        self.errors.last_mut().unwrap()
+    }
+    pub fn push_warning(&mut self, span:Span, describe:&str) -> &mut DiagUnit {
+        self.warnings.push(DiagUnit { span, describe: String::from(describe), notes: Vec::new() });
+        self.warnings.last_mut().unwrap()
+    }
+
+    // the Display but with file name, row and col from source
+    pub fn render(&self, source:&Source) -> String {
+        if self.errors.is_empty() && self.warnings.is_empty() {
+            return String::from("ok")
+        }
+        let mut text = String::new();
+        for a in &self.errors {
+            text += &format!("error {}\n", a.render(source));
+        }
+        for a in &self.warnings {
+            text += &format!("warning {}\n", a.render(source));
+        }
+        text + &format!("{} error(s), {} warning(s)", self.errors.len(), self.warnings.len())
     }
 }
 
 pub fn make() -> Object { Object::new() }
 
+impl fmt::Display for DiagUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}: {}", self.span.file, self.span, self.describe)?;
+        for (span, describe) in &self.notes {
+            write!(f, "; note {}@{}: {}", span.file, span, describe)?;
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", "ok")
+        if self.errors.is_empty() && self.warnings.is_empty() {
+            return write!(f, "ok")
+        }
+        for a in &self.errors {
+            writeln!(f, "error {}", a)?;
+        }
+        for a in &self.warnings {
+            writeln!(f, "warning {}", a)?;
+        }
+        write!(f, "{} error(s), {} warning(s)", self.errors.len(), self.warnings.len())
     }
 }
@@ -26,6 +26,10 @@ pub type Diag = diag::Object;
 pub mod pool;
 pub type Pool = pool::Object;
 
+pub mod source;
+pub type Source = source::Object;
+pub type Span = source::Span;
+
 pub trait Emit {
     fn emit(&self, pool:&Pool) -> String;
 }
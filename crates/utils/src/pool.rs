@@ -24,7 +24,7 @@ use std::collections::HashMap;
 
 pub type Id = u32;
 
-#[derive(Debug)]
+#[derive(Debug,Default)]
 #[allow(dead_code)]
 pub struct Object {
     str_index: HashMap<String, Id>,
@@ -60,6 +60,11 @@ impl Object {
             String::new()
         }
     }
+
+    // the strings in order of added
+    pub fn strs(&self) -> impl Iterator<Item = &str> {
+        self.str_heap.iter().map(|a| a.as_str())
+    }
 }
 
 pub fn make() -> Object { Object::new() }
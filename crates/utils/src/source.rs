@@ -0,0 +1,141 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use core::fmt;
+
+pub type FileId = u32;
+
+// A range [start, end) of bytes in a file, the row and col of it are computed by Object when need
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Span {
+    pub fn new(file:FileId, start:usize, end:usize) -> Span {
+        Span { file, start: start as u32, end: end as u32 }
+    }
+    // the span cover both self and other, they should in same file
+    pub fn to(&self, other:Span) -> Span {
+        Span { file: self.file, start: self.start.min(other.start), end: self.end.max(other.end) }
+    }
+    pub fn len(&self) -> u32 {self.end - self.start}
+    pub fn is_empty(&self) -> bool {self.end == self.start}
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+// How to count the col, by Unicode chars or by UTF-16 code units (for editors)
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub enum ColumnUnit {
+    #[default]
+    Char,
+    Utf16,
+}
+
+#[derive(Debug)]
+pub struct File {
+    name: String,
+    code: Vec<u8>,
+    line_starts: Vec<u32>,
+}
+
+#[derive(Debug,Default)]
+pub struct Object {
+    files: Vec<File>,
+    column_unit: ColumnUnit,
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object { files: Vec::new(), column_unit: ColumnUnit::Char }
+    }
+
+    pub fn add_file(&mut self, name:impl ToString, code:Vec<u8>) -> FileId {
+        let mut line_starts = vec![0];
+        for (i, &a) in code.iter().enumerate() {
+            if a == b'\n' {
+                line_starts.push(i as u32 + 1);
+            }
+        }
+        self.files.push(File { name: name.to_string(), code, line_starts });
+        (self.files.len() - 1) as FileId
+    }
+
+    // the ids of files are 0..file_count
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn set_column_unit(&mut self, unit:ColumnUnit) {
+        self.column_unit = unit
+    }
+
+    pub fn name(&self, file:FileId) -> &str {
+        self.files.get(file as usize).map_or("", |a| a.name.as_str())
+    }
+
+    pub fn code(&self, file:FileId) -> &[u8] {
+        self.files.get(file as usize).map_or(&[], |a| a.code.as_slice())
+    }
+
+    // the (row, col) of byte offset in file, both start at 1
+    pub fn row_col(&self, file:FileId, offset:u32) -> (u32, u32) {
+        let Some(the_file) = self.files.get(file as usize) else {
+            return (0, 0)
+        };
+        let offset = offset.min(the_file.code.len() as u32);
+        let row = the_file.line_starts.partition_point(|&a| a <= offset);
+        let line_start = the_file.line_starts[row - 1] as usize;
+        let before = String::from_utf8_lossy(&the_file.code[line_start..offset as usize]);
+        let col = match self.column_unit {
+            ColumnUnit::Char => {before.chars().count()}
+            ColumnUnit::Utf16 => {before.encode_utf16().count()}
+        };
+        (row as u32, col as u32 + 1)
+    }
+
+    pub fn start_of(&self, span:Span) -> (u32, u32) {
+        self.row_col(span.file, span.start)
+    }
+
+    pub fn text_of(&self, span:Span) -> String {
+        let code = self.code(span.file);
+        let end = (span.end as usize).min(code.len());
+        let start = (span.start as usize).min(end);
+        String::from_utf8_lossy(&code[start..end]).into_owned()
+    }
+
+    // like "main.fa:3:5"
+    pub fn locate(&self, span:Span) -> String {
+        let (row, col) = self.start_of(span);
+        format!("{}:{}:{}", self.name(span.file), row, col)
+    }
+}
+
+pub fn make() -> Object { Object::new() }
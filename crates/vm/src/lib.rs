@@ -20,34 +20,499 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-#[derive(Debug)]
-#[allow(dead_code)]
-pub struct Object {
-    stack:Vec<u8>,
+use core::fmt;
+use std::{collections::HashMap, rc::Rc};
+
+pub mod proto;
+
+// The value at runtime
+#[derive(Clone,Debug,Default,PartialEq)]
+pub enum Value {
+    #[default]
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Char(char),
+    String(String),
+    List(Rc<Vec<Value>>),
+    Object(proto::Ref),
+    Function(usize),            // index of Program.functions
+    Builtin(Builtin),
+    Dispatch(usize),            // index of Program.groups
+    Pipe(Rc<Vec<Value>>),       // the functions called in order, the result of one is the argument of next
 }
-impl Object {
-    pub fn new() -> Object {
-        Object {
-            stack: Vec::new(),
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => {"Nil"}
+            Value::Bool(_) => {"Bool"}
+            Value::Integer(_) => {"Number"}
+            Value::Float(_) => {"Float"}
+            Value::Char(_) => {"Char"}
+            Value::String(_) => {"String"}
+            Value::List(_) => {"List"}
+            Value::Object(_) => {"Object"}
+            Value::Function(_) | Value::Builtin(_) | Value::Dispatch(_) | Value::Pipe(_) => {"Function"}
         }
     }
 }
-pub fn make() -> Object {Object::new()}
 
-#[derive(Debug)]
-#[allow(dead_code,non_camel_case_types)]
-pub struct SEI_Apply {}
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => {write!(f, "nil")}
+            Value::Bool(a) => {write!(f, "{}", a)}
+            Value::Integer(a) => {write!(f, "{}", a)}
+            Value::Float(a) => {write!(f, "{:?}", a)}
+            Value::Char(a) => {write!(f, "{}", a)}
+            Value::String(a) => {write!(f, "{}", a)}
+            Value::List(a) => {
+                let items:Vec<String> = a.iter().map(|a| a.to_string()).collect();
+                write!(f, "[{}]", items.join(" "))
+            }
+            Value::Object(a) => {write!(f, "{}", a)}
+            Value::Function(a) => {write!(f, "<function {}>", a)}
+            Value::Builtin(a) => {write!(f, "<builtin {}>", a.name)}
+            Value::Dispatch(a) => {write!(f, "<dispatch {}>", a)}
+            Value::Pipe(a) => {write!(f, "<pipe of {}>", a.len())}
+        }
+    }
+}
 
-#[derive(Debug)]
-#[allow(dead_code)]
+// The function of host, like the "println!" and "+" from farvm_std
+//   the Host is for the builtins of higher order, like "each", they call the values by it
+#[derive(Clone,Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub call: fn(&mut Host, &[Value]) -> Result<Value, String>,
+}
+impl PartialEq for Builtin {
+    fn eq(&self, other:&Builtin) -> bool {self.name == other.name}
+}
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Builtin({})", self.name)
+    }
+}
+
+// The instruction of stack machine, the operands are poped from the stack and the result is pushed
+#[derive(Clone,Debug,PartialEq)]
+pub enum Instruction {
+    Push(Value),
+    Pop,
+    Load(usize),                                // the local of frame
+    Store(usize),
+    LoadGlobal(usize),                          // index of Program.globals
+    StoreGlobal(usize),
+    LoadBuiltin(String),                        // the builtin is find by name when run
+    Call(usize),                                // callee and the count of arguments after it
+    Dispatch{group:usize, argc:usize},          // the arguments, the method is chosen by their types
+    Return,
+    MakeList(usize),
+    MakeObject{slots:Vec<String>, parent:bool}, // the parent if has, then the values of slots
+    GetSlot(String),                            // object
+    SetSlot(String),                            // object, value
+    Format(usize),                              // the values are joined to a String
+}
+
+// The type of parameter tested at runtime, the widths of number aren't kept at runtime
+//   the Number is Integer or Float, the Proto is the objects derived from the object of global
+#[derive(Clone,Debug,PartialEq)]
+pub enum TypeTest {
+    Any,
+    Nil,
+    Bool,
+    Char,
+    String,
+    Number,
+    Integer,
+    Float,
+    List,
+    Function,
+    Object,
+    Proto(usize),                               // index of Program.globals
+}
+
+impl fmt::Display for TypeTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeTest::Any => {write!(f, "Any")}
+            TypeTest::Nil => {write!(f, "Nil")}
+            TypeTest::Bool => {write!(f, "Bool")}
+            TypeTest::Char => {write!(f, "Char")}
+            TypeTest::String => {write!(f, "String")}
+            TypeTest::Number => {write!(f, "Number")}
+            TypeTest::Integer => {write!(f, "Integer")}
+            TypeTest::Float => {write!(f, "Float")}
+            TypeTest::List => {write!(f, "List")}
+            TypeTest::Function => {write!(f, "Function")}
+            TypeTest::Object => {write!(f, "Object")}
+            TypeTest::Proto(a) => {write!(f, "<proto {}>", a)}
+        }
+    }
+}
+
+// The methods of dispatch group, they're sorted by specificity, the first applicable is chosen
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub methods: Vec<Method>,
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct Method {
+    pub params: Vec<TypeTest>,
+    pub function: usize,
+}
+
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub arity: usize,
+    pub locals: usize,                          // count of locals, the params are the first
+    pub code: Vec<Instruction>,
+}
+
+#[derive(Debug,Default)]
 pub struct Program {
+    pub functions: Vec<Function>,
+    pub globals: Vec<String>,
+    pub groups: Vec<Group>,
+    pub init: usize,                            // the function set the globals
 }
 impl Program {
     pub fn new() -> Program {
         Program {
+            functions: Vec::new(),
+            globals: Vec::new(),
+            groups: Vec::new(),
+            init: 0,
+        }
+    }
+    pub fn global_of(&self, name:&str) -> Option<usize> {
+        self.globals.iter().position(|a| a == name)
+    }
+    // like "(Number Float)", the Proto is the name of global
+    pub fn show(&self, params:&[TypeTest]) -> String {
+        let params:Vec<String> = params.iter().map(|a| match a {
+            TypeTest::Proto(i) => {self.globals.get(*i).cloned().unwrap_or_else(|| a.to_string())}
+            a => {a.to_string()}
+        }).collect();
+        format!("({})", params.join(" "))
+    }
+}
+
+// The runtime type of argument for key of dispatch table
+//   the object is keyed by the prototypes of group it's derived from, so the object with same key is applicable to same methods
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+enum Kind {
+    Nil,
+    Bool,
+    Integer,
+    Float,
+    Char,
+    String,
+    List,
+    Function,
+    Object(Vec<usize>),                         // the globals of Proto tested by group
+}
+
+// The methods chosen before for the kinds of arguments, the index of Group.methods
+type Table = HashMap<Vec<Kind>, usize>;
+
+#[derive(Debug)]
+struct Frame {
+    function: usize,
+    ip: usize,
+    base: usize,                                // the first local in stack
+}
+
+#[derive(Debug,Default)]
+pub struct Object {
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    globals: Vec<Value>,
+    builtins: HashMap<String, Builtin>,
+    tables: Vec<Table>,                         // the dispatch table of Program.groups
+    protos: HashMap<usize, Vec<usize>>,         // the groups tested the Proto of global, their entries are dropped when it's stored
+}
+impl Object {
+    pub fn new() -> Object {
+        Object {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: Vec::new(),
+            builtins: HashMap::new(),
+            tables: Vec::new(),
+            protos: HashMap::new(),
         }
     }
+
+    pub fn install(&mut self, builtin:Builtin) {
+        self.builtins.insert(String::from(builtin.name), builtin);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap_or_default()
+    }
+    fn pop_n(&mut self, n:usize) -> Vec<Value> {
+        let at = self.stack.len().saturating_sub(n);
+        self.stack.split_off(at)
+    }
+
+    // call the callee with args on the stack, the function push a frame and the builtin is done now
+    fn call(&mut self, program:&Program, argc:usize) -> Result<(), String> {
+        let at = self.stack.len() - argc - 1;
+        match self.stack[at].clone() {
+            Value::Function(i) => {
+                let function = &program.functions[i];
+                if function.arity != argc {
+                    return Err(format!("function '{}' expects {} argument(s) but found {}", function.name, function.arity, argc))
+                }
+                self.stack.resize(at + 1 + function.locals, Value::Nil);
+                self.frames.push(Frame { function: i, ip: 0, base: at + 1 });
+                Ok(())
+            }
+            Value::Builtin(a) => {
+                let args = self.pop_n(argc);
+                self.pop();
+                let result = (a.call)(&mut Host { cpu: self, program }, &args).map_err(|e| format!("{}: {}", a.name, e))?;
+                self.stack.push(result);
+                Ok(())
+            }
+            Value::Dispatch(i) => {
+                self.stack[at] = Value::Function(self.dispatch(program, i, argc)?);
+                self.call(program, argc)
+            }
+            // the first is called with args, the others with the result before
+            Value::Pipe(functions) => {
+                let args = self.pop_n(argc);
+                self.pop();
+                let mut functions = functions.iter();
+                let mut result = match functions.next() {
+                    Some(first) => {self.call_value(program, first.clone(), args)?}
+                    None => {return Err(String::from("the pipe has no function"))}
+                };
+                for a in functions {
+                    result = self.call_value(program, a.clone(), vec![result])?;
+                }
+                self.stack.push(result);
+                Ok(())
+            }
+            a => {Err(format!("{} '{}' is not callable", a.type_name(), a))}
+        }
+    }
+
+    // the function of method for the args on the stack, it's found in table or the first applicable of group
+    fn dispatch(&mut self, program:&Program, i:usize, argc:usize) -> Result<usize, String> {
+        let group = &program.groups[i];
+        let args = &self.stack[self.stack.len() - argc..];
+        let key:Vec<Kind> = args.iter().map(|a| self.kind_of(group, a)).collect();
+        if let Some(m) = self.tables.get(i).and_then(|a| a.get(&key)) {
+            return Ok(group.methods[*m].function)
+        }
+        let Some(m) = group.methods.iter().position(|m| self.is_applicable(m, args)) else {
+            let types:Vec<&str> = args.iter().map(|a| a.type_name()).collect();
+            let candidates:Vec<String> = group.methods.iter().map(|m| program.show(&m.params)).collect();
+            return Err(format!("no method of '{}' is applicable to ({}), the candidates are {}",
+                group.name, types.join(" "), candidates.join(" ")))
+        };
+        if self.tables.len() <= i {
+            self.tables.resize(program.groups.len(), Table::new());
+        }
+        self.tables[i].insert(key, m);
+        Ok(group.methods[m].function)
+    }
+
+    fn kind_of(&self, group:&Group, value:&Value) -> Kind {
+        match value {
+            Value::Nil => {Kind::Nil}
+            Value::Bool(_) => {Kind::Bool}
+            Value::Integer(_) => {Kind::Integer}
+            Value::Float(_) => {Kind::Float}
+            Value::Char(_) => {Kind::Char}
+            Value::String(_) => {Kind::String}
+            Value::List(_) => {Kind::List}
+            Value::Function(_) | Value::Builtin(_) | Value::Dispatch(_) | Value::Pipe(_) => {Kind::Function}
+            Value::Object(_) => {
+                let mut protos:Vec<usize> = group.methods.iter().flat_map(|m| &m.params).filter_map(|t| match t {
+                    TypeTest::Proto(global) if self.is_instance(value, t) => {Some(*global)}
+                    _ => {None}
+                }).collect();
+                protos.sort_unstable();
+                protos.dedup();
+                Kind::Object(protos)
+            }
+        }
+    }
+
+    fn is_applicable(&self, method:&Method, args:&[Value]) -> bool {
+        method.params.len() == args.len() && method.params.iter().zip(args).all(|(t, a)| self.is_instance(a, t))
+    }
+
+    fn is_instance(&self, value:&Value, test:&TypeTest) -> bool {
+        match (test, value) {
+            (TypeTest::Any, _) => {true}
+            (TypeTest::Nil, Value::Nil) | (TypeTest::Bool, Value::Bool(_)) => {true}
+            (TypeTest::Char, Value::Char(_)) | (TypeTest::String, Value::String(_)) => {true}
+            (TypeTest::Number, Value::Integer(_) | Value::Float(_)) => {true}
+            (TypeTest::Integer, Value::Integer(_)) | (TypeTest::Float, Value::Float(_)) => {true}
+            (TypeTest::List, Value::List(_)) | (TypeTest::Object, Value::Object(_)) => {true}
+            (TypeTest::Function, Value::Function(_) | Value::Builtin(_) | Value::Dispatch(_) | Value::Pipe(_)) => {true}
+            (TypeTest::Proto(global), Value::Object(a)) => {
+                let Some(Value::Object(proto)) = self.globals.get(*global) else {
+                    return false
+                };
+                std::iter::successors(Some(a.clone()), |a| a.parent()).any(|a| a == *proto)
+            }
+            _ => {false}
+        }
+    }
+
+    fn step(&mut self, program:&Program, instruction:&Instruction) -> Result<(), String> {
+        match instruction {
+            Instruction::Push(a) => {self.stack.push(a.clone())}
+            Instruction::Pop => {self.pop();}
+            Instruction::Load(i) => {
+                let base = self.frames.last().map_or(0, |a| a.base);
+                self.stack.push(self.stack[base + i].clone());
+            }
+            Instruction::Store(i) => {
+                let base = self.frames.last().map_or(0, |a| a.base);
+                self.stack[base + i] = self.pop();
+            }
+            Instruction::LoadGlobal(i) => {self.stack.push(self.globals[*i].clone())}
+            Instruction::StoreGlobal(i) => {
+                self.globals[*i] = self.pop();
+                for group in self.protos.get(i).into_iter().flatten() {
+                    self.tables[*group].clear();
+                }
+            }
+            Instruction::LoadBuiltin(name) => {
+                match self.builtins.get(name) {
+                    Some(a) => {self.stack.push(Value::Builtin(*a))}
+                    None => {return Err(format!("undefined '{}'", name))}
+                }
+            }
+            Instruction::Call(argc) => {self.call(program, *argc)?}
+            Instruction::Dispatch{group, argc} => {
+                let function = self.dispatch(program, *group, *argc)?;
+                let at = self.stack.len() - argc;
+                self.stack.insert(at, Value::Function(function));
+                self.call(program, *argc)?
+            }
+            Instruction::Return => {
+                let result = self.pop();
+                if let Some(frame) = self.frames.pop() {
+                    self.stack.truncate(frame.base - 1);
+                }
+                self.stack.push(result);
+            }
+            Instruction::MakeList(n) => {
+                let items = self.pop_n(*n);
+                self.stack.push(Value::List(Rc::new(items)));
+            }
+            Instruction::MakeObject{slots, parent} => {
+                let values = self.pop_n(slots.len());
+                let parent = match parent {
+                    false => {None}
+                    true => {
+                        match self.pop() {
+                            Value::Object(a) => {Some(a)}
+                            a => {return Err(format!("the parent should be Object but found {}", a.type_name()))}
+                        }
+                    }
+                };
+                let slots = slots.iter().cloned().zip(values).collect();
+                self.stack.push(Value::Object(proto::make(parent, slots)));
+            }
+            Instruction::GetSlot(name) => {
+                let value = match self.pop() {
+                    Value::Object(a) => {
+                        a.get(name).ok_or_else(|| format!("no slot '{}' in {}", name, a))?
+                    }
+                    a => {return Err(format!("{} has no slot '{}'", a.type_name(), name))}
+                };
+                self.stack.push(value);
+            }
+            Instruction::SetSlot(name) => {
+                let value = self.pop();
+                match self.pop() {
+                    Value::Object(a) => {a.set(name, value)}
+                    a => {return Err(format!("{} has no slot '{}'", a.type_name(), name))}
+                }
+            }
+            Instruction::Format(n) => {
+                let text:String = self.pop_n(*n).iter().map(|a| a.to_string()).collect();
+                self.stack.push(Value::String(text));
+            }
+        }
+        Ok(())
+    }
+
+    // run until the frame at depth is returned, the result is on the stack
+    fn run_to(&mut self, program:&Program, depth:usize) -> Result<(), String> {
+        while self.frames.len() > depth {
+            let frame = self.frames.last_mut().unwrap();
+            let function = &program.functions[frame.function];
+            let Some(instruction) = function.code.get(frame.ip) else {
+                return Err(format!("function '{}' is end without return", function.name))
+            };
+            frame.ip += 1;
+            self.step(program, instruction)?;
+        }
+        Ok(())
+    }
+
+    // call the function with args, it's the entry from host
+    pub fn call_function(&mut self, program:&Program, function:usize, args:Vec<Value>) -> Result<Value, String> {
+        self.call_value(program, Value::Function(function), args)
+    }
+
+    // call any callable value with args, the builtin is returned at once
+    pub fn call_value(&mut self, program:&Program, callee:Value, args:Vec<Value>) -> Result<Value, String> {
+        let argc = args.len();
+        self.stack.push(callee);
+        self.stack.extend(args);
+        let depth = self.frames.len();
+        self.call(program, argc)?;
+        self.run_to(program, depth)?;
+        Ok(self.pop())
+    }
 }
 
-pub fn run(me:&mut Object, program:&Program) {
+// The VM and the program running, it's given to the builtin when it's called
+pub struct Host<'a> {
+    cpu: &'a mut Object,
+    program: &'a Program,
+}
+impl<'a> Host<'a> {
+    pub fn call(&mut self, callee:&Value, args:Vec<Value>) -> Result<Value, String> {
+        self.cpu.call_value(self.program, callee.clone(), args)
+    }
+}
+pub fn make() -> Object {Object::new()}
+
+// run the init of program, then the "main" if it's defined, the result is of "main"
+pub fn run(me:&mut Object, program:&Program) -> Result<Value, String> {
+    me.globals = vec![Value::Nil; program.globals.len()];
+    me.tables = vec![Table::new(); program.groups.len()];
+    me.protos = HashMap::new();
+    for (i, group) in program.groups.iter().enumerate() {
+        for test in group.methods.iter().flat_map(|m| &m.params) {
+            if let TypeTest::Proto(global) = test {
+                let groups = me.protos.entry(*global).or_default();
+                if !groups.contains(&i) {
+                    groups.push(i);
+                }
+            }
+        }
+    }
+    me.call_function(program, program.init, Vec::new())?;
+    match program.global_of("main").map(|i| me.globals[i].clone()) {
+        Some(Value::Function(main)) => {me.call_function(program, main, Vec::new())}
+        _ => {Ok(Value::Nil)}
+    }
 }
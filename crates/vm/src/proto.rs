@@ -0,0 +1,88 @@
+// MIT License
+
+// Copyright (c) 2023 clonne
+
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use core::fmt;
+use std::{cell::RefCell, rc::Rc};
+use super::Value;
+
+// The object at runtime, it's slots and the parent as prototype
+//   get a slot is find in self then the parents, set a slot is always in self
+#[derive(Default)]
+pub struct Object {
+    parent: Option<Ref>,
+    slots: Vec<(String, Value)>,
+}
+
+// the shared object, the same object is equal, not the same slots
+#[derive(Clone)]
+pub struct Ref(Rc<RefCell<Object>>);
+
+pub fn make(parent:Option<Ref>, slots:Vec<(String, Value)>) -> Ref {
+    Ref(Rc::new(RefCell::new(Object { parent, slots })))
+}
+
+impl Ref {
+    pub fn parent(&self) -> Option<Ref> {
+        self.0.borrow().parent.clone()
+    }
+    pub fn get(&self, name:&str) -> Option<Value> {
+        let me = self.0.borrow();
+        match me.slots.iter().find(|(a, _)| a == name) {
+            Some((_, value)) => {Some(value.clone())}
+            None => {me.parent.as_ref().and_then(|a| a.get(name))}
+        }
+    }
+    pub fn set(&self, name:&str, value:Value) {
+        let mut me = self.0.borrow_mut();
+        match me.slots.iter_mut().find(|(a, _)| a == name) {
+            Some((_, a)) => {*a = value}
+            None => {me.slots.push((String::from(name), value))}
+        }
+    }
+}
+
+impl PartialEq for Ref {
+    fn eq(&self, other:&Ref) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// only the names of own slots, the values can be the object itself
+impl fmt::Debug for Ref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let me = self.0.borrow();
+        let names:Vec<&str> = me.slots.iter().map(|(a, _)| a.as_str()).collect();
+        write!(f, "@{{{}}}", names.join(" "))
+    }
+}
+
+// like @{x: 1 y: 2}, the slots of parents and the objects in slots are not shown
+impl fmt::Display for Ref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let me = self.0.borrow();
+        let slots:Vec<String> = me.slots.iter().map(|(name, value)| match value {
+            Value::Object(_) => {format!("{}: @{{..}}", name)}
+            _ => {format!("{}: {}", name, value)}
+        }).collect();
+        write!(f, "@{{{}}}", slots.join(" "))
+    }
+}